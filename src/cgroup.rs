@@ -0,0 +1,230 @@
+//! Interaction between I/O priorities and the block I/O controller of cgroups.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use nix::errno::Errno;
+
+use super::procfs::io_error;
+use super::*;
+
+/// How the I/O priority of a process combines with the BFQ weight of its cgroup, as returned by
+/// [`combined_effective_priority`].
+///
+/// BFQ schedules I/O in two layers. First, the bandwidth of each device is divided between cgroups
+/// in proportion to their weights, and only then is the share of each cgroup divided between its
+/// processes, according to their I/O priorities. An I/O priority thus only ranks a process relative
+/// to the other processes in the same cgroup, and a real-time process in a cgroup with a low weight
+/// may well receive less bandwidth than a best-effort process in a cgroup with a high weight.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CombinedPriority {
+    priority: Priority,
+    cgroup: PathBuf,
+    weight: Option<u16>,
+}
+impl CombinedPriority {
+    /// Get the I/O priority of the process itself, which applies within its cgroup.
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+    /// Get the path of the cgroup of the process, relative to the root of the hierarchy that
+    /// controls block I/O.
+    pub fn cgroup(&self) -> &Path {
+        &self.cgroup
+    }
+    /// Get the default BFQ weight of the cgroup, ranging from 1 to 1000 with 100 being the
+    /// default, which applies between cgroups.
+    ///
+    /// This is [`None`] if the cgroup does not expose a BFQ weight, which is the case for the root
+    /// cgroup, or when BFQ is not available.
+    pub fn weight(&self) -> Option<u16> {
+        self.weight
+    }
+}
+
+/// Parse a weight file, which either contains a single weight (cgroup v1), or a `default` line
+/// followed by per-device overrides (cgroup v2).
+fn parse_weight(contents: &str) -> Option<u16> {
+    contents.lines().find_map(|line| {
+        let weight = line.strip_prefix("default ").unwrap_or(line);
+        weight.trim().parse().ok()
+    })
+}
+
+/// Read both the I/O priority of a process, and the BFQ weight of the cgroup it belongs to.
+///
+/// A PID of zero refers to the calling thread. The weight is read from `io.bfq.weight` for cgroup
+/// v2, and from `blkio.bfq.weight` for the `blkio` controller of cgroup v1, both of which are
+/// assumed to be mounted at `/sys/fs/cgroup`. Other weight files, such as `io.weight` of the
+/// `io.cost` controller, use different ranges and semantics, and are thus never read. Refer to
+/// [`CombinedPriority`] for how the two layers interact.
+pub fn combined_effective_priority(pid: Pid) -> Result<CombinedPriority> {
+    let priority = get_priority(Target::Process(pid))?;
+
+    let proc_dir = if pid.as_raw() == 0 {
+        "thread-self".to_owned()
+    } else {
+        pid.to_string()
+    };
+    let cgroups = fs::read_to_string(format!("/proc/{}/cgroup", proc_dir)).map_err(io_error)?;
+
+    let mut v1 = None;
+    let mut v2 = None;
+
+    // Each line is of the form `hierarchy-id:controller-list:path`, where cgroup v2 has the
+    // hierarchy ID 0 and no controllers.
+    for line in cgroups.lines() {
+        let mut fields = line.splitn(3, ':');
+
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some("0"), Some(""), Some(path)) => v2 = Some(path),
+            (Some(_), Some(controllers), Some(path))
+                if controllers.split(',').any(|c| c == "blkio") =>
+            {
+                v1 = Some(path)
+            }
+            _ => continue,
+        }
+    }
+
+    let mut candidates = Vec::new();
+
+    if let Some(path) = v1 {
+        candidates.push((path, "/sys/fs/cgroup/blkio", "blkio.bfq.weight"));
+    }
+    if let Some(path) = v2 {
+        candidates.push((path, "/sys/fs/cgroup", "io.bfq.weight"));
+        candidates.push((path, "/sys/fs/cgroup/unified", "io.bfq.weight"));
+    }
+
+    for &(path, mount, file) in &candidates {
+        let dir = Path::new(mount).join(path.trim_start_matches('/'));
+
+        match fs::read_to_string(dir.join(file)) {
+            Ok(contents) => {
+                let weight = parse_weight(&contents).ok_or(Error::from(Errno::EINVAL))?;
+
+                return Ok(CombinedPriority {
+                    priority,
+                    cgroup: PathBuf::from(path),
+                    weight: Some(weight),
+                });
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => return Err(io_error(error)),
+        }
+    }
+
+    Ok(CombinedPriority {
+        priority,
+        cgroup: PathBuf::from(v1.or(v2).unwrap_or("/")),
+        weight: None,
+    })
+}
+
+/// Convert an error from accessing a cgroup file, unlike [`io_error`] keeping `ENOENT` as-is.
+fn cgroup_error(error: io::Error) -> Error {
+    Errno::from_i32(error.raw_os_error().unwrap_or(libc::EIO)).into()
+}
+
+/// Thaws a frozen cgroup when dropped.
+struct ThawGuard<'a> {
+    cgroup: &'a Path,
+}
+impl Drop for ThawGuard<'_> {
+    fn drop(&mut self) {
+        let _ = fs::write(self.cgroup.join("cgroup.freeze"), "0");
+    }
+}
+
+/// Determine whether the calling thread, or the process it belongs to, is a member of a cgroup or
+/// any of its descendants.
+fn contains_caller(cgroup: &Path) -> Result<bool> {
+    let cgroup = fs::canonicalize(cgroup).map_err(cgroup_error)?;
+
+    for proc_dir in &["self", "thread-self"] {
+        let cgroups = fs::read_to_string(format!("/proc/{}/cgroup", proc_dir)).map_err(io_error)?;
+
+        let path = match cgroups.lines().find_map(|line| line.strip_prefix("0::")) {
+            Some(path) => path,
+            None => continue,
+        };
+        for mount in &["/sys/fs/cgroup", "/sys/fs/cgroup/unified"] {
+            // Only the root of a cgroup v2 hierarchy has this file, unlike the tmpfs that holds
+            // the cgroup v1 hierarchies on hybrid systems.
+            if !Path::new(mount).join("cgroup.controllers").exists() {
+                continue;
+            }
+            let dir = Path::new(mount).join(path.trim_start_matches('/'));
+
+            match fs::canonicalize(dir) {
+                Ok(dir) if dir.starts_with(&cgroup) => return Ok(true),
+                _ => continue,
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Set the I/O priority of every thread in a cgroup, while the cgroup is frozen.
+///
+/// The cgroup is given by the path of its directory, such as `/sys/fs/cgroup/my-service`. It is
+/// frozen by writing to `cgroup.freeze`, after which every thread listed in `cgroup.threads` is
+/// set to the priority, before the cgroup is thawed again. This prevents members from changing
+/// their own priorities or spawning new threads midway, which would otherwise leave the group
+/// inconsistent, at the cost of pausing the entire cgroup for the duration. The cgroup is always
+/// thawed, even if an error occurs, unless it was already frozen beforehand, in which case it is
+/// left frozen.
+///
+/// Since freezing a cgroup that the caller belongs to would freeze the caller itself, and thus
+/// never return, `EDEADLK` is returned if the calling thread or its process is a member of the
+/// cgroup or any of its descendants, as determined through `/proc/self/cgroup`, assuming that
+/// cgroup v2 is mounted at `/sys/fs/cgroup` (or `/sys/fs/cgroup/unified`).
+///
+/// This requires cgroup v2 (Linux 5.2 or later), and write access to the `cgroup.freeze` file of
+/// the cgroup, in addition to the usual permissions for setting the priority of each thread.
+/// Freezing is asynchronous, so this waits up to one second for the `frozen` entry of
+/// `cgroup.events` to be set, before proceeding regardless.
+///
+/// The priority is set for each thread individually, with the result of each returned in the
+/// order of `cgroup.threads`, whereas an error is only returned if freezing or enumerating the
+/// cgroup fails.
+pub fn set_frozen_group_priority(
+    cgroup: &Path,
+    priority: Priority,
+) -> Result<Vec<(Pid, Result<()>)>> {
+    if contains_caller(cgroup)? {
+        return Err(Errno::EDEADLK.into());
+    }
+    let freeze = cgroup.join("cgroup.freeze");
+    let already_frozen = fs::read_to_string(&freeze).map_err(cgroup_error)?.trim() == "1";
+
+    let _guard = if already_frozen {
+        None
+    } else {
+        fs::write(&freeze, "1").map_err(cgroup_error)?;
+        Some(ThawGuard { cgroup })
+    };
+
+    for _ in 0..1000 {
+        let events = fs::read_to_string(cgroup.join("cgroup.events")).map_err(cgroup_error)?;
+
+        if events.lines().any(|line| line.trim() == "frozen 1") {
+            break;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    let threads = fs::read_to_string(cgroup.join("cgroup.threads")).map_err(cgroup_error)?;
+
+    Ok(threads
+        .lines()
+        .filter_map(|line| line.trim().parse().ok())
+        .map(|tid| {
+            let tid = Pid::from_raw(tid);
+            (tid, set_priority(Target::Process(tid), priority))
+        })
+        .collect())
+}
@@ -0,0 +1,71 @@
+//! Setting the I/O priority of child processes before they are executed.
+use std::io;
+use std::os::unix::process::CommandExt as _;
+use std::process::Command;
+
+use super::*;
+
+mod private {
+    pub trait Sealed {}
+}
+impl private::Sealed for Command {}
+
+/// An extension trait for [`Command`], that allows spawning processes with a specific I/O
+/// priority.
+pub trait CommandExt: private::Sealed {
+    /// Set the I/O priority of the child process, before the program is executed.
+    ///
+    /// The priority is set from within the forked child, using
+    /// [`pre_exec`](std::os::unix::process::CommandExt::pre_exec), and thus applies from the very
+    /// first instruction of the program, without any window in which it runs at the priority of
+    /// the parent. If setting the priority fails, the child exits before executing the program,
+    /// and the error is instead returned by [`spawn`](Command::spawn) or any of the other methods
+    /// that spawn the process.
+    ///
+    /// The hook only issues a single `ioprio_set` syscall, which is async-signal-safe and neither
+    /// allocates nor takes any locks, and thus upholds the safety requirements of `pre_exec`, even
+    /// when the parent is multithreaded. Note that the hook runs after any hooks registered
+    /// earlier, and that `posix_spawn` can no longer be used to spawn the process.
+    fn io_priority(&mut self, priority: Priority) -> &mut Self;
+}
+impl CommandExt for Command {
+    fn io_priority(&mut self, priority: Priority) -> &mut Self {
+        unsafe {
+            self.pre_exec(move || {
+                set_current_priority(priority)
+                    .map_err(|error| io::Error::from_raw_os_error(error.raw() as i32))
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn children_start_at_the_priority() {
+        let mut child = Command::new("sleep")
+            .arg("10")
+            .io_priority(Priority::idle())
+            .spawn()
+            .unwrap();
+
+        // The hook has already run once spawning returns, since the program has been executed.
+        let priority = get_priority(Target::Process(Pid::from_raw(child.id() as i32)));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert_eq!(priority, Ok(Priority::idle()));
+    }
+    #[test]
+    fn failing_to_set_the_priority_fails_the_spawn() {
+        let error = Command::new("true")
+            .io_priority(Priority::from_inner(0xFFFF))
+            .spawn()
+            .unwrap_err();
+
+        assert_eq!(error.raw_os_error(), Some(libc::EINVAL));
+    }
+}
@@ -0,0 +1,127 @@
+//! Setting the I/O priority of `glommio` executors, from within their threads.
+//!
+//! Glommio runs every executor on a thread of its own, and never moves tasks between threads, so
+//! unlike with work-stealing executors, the per-thread I/O priority reliably applies to all I/O of
+//! the tasks of an executor. This includes I/O submitted through its io_uring rings without an
+//! explicit priority, which the kernel issues at the priority of the submitting thread.
+use std::future::Future;
+
+use glommio_::{
+    ExecutorJoinHandle, LocalExecutorBuilder, LocalExecutorPoolBuilder, PoolThreadHandles,
+};
+
+use super::*;
+
+mod private {
+    pub trait Sealed {}
+}
+impl private::Sealed for LocalExecutorBuilder {}
+impl private::Sealed for LocalExecutorPoolBuilder {}
+
+/// Run the future generated after setting the I/O priority of the executor thread, or fail with
+/// the error if setting it failed.
+async fn run_prioritized<F: Future>(fut: Result<F>) -> Result<F::Output> {
+    Ok(fut?.await)
+}
+
+/// An extension trait for [`glommio::LocalExecutorBuilder`](LocalExecutorBuilder), that allows
+/// spawning executors with a specific I/O priority.
+pub trait LocalExecutorBuilderExt: private::Sealed {
+    /// Spawn the executor, like [`spawn`](LocalExecutorBuilder::spawn), but set the I/O priority
+    /// of its thread before generating the future.
+    ///
+    /// The priority is set from within the executor thread, and thus applies to every task of the
+    /// executor, whereas threads created by glommio while setting up the executor, such as those
+    /// of its blocking thread pool, keep their own priority. If setting the priority fails, the
+    /// future is never generated, and the error is instead returned when joining the executor.
+    fn spawn_with_priority<G, F, T>(
+        self,
+        priority: Priority,
+        fut_gen: G,
+    ) -> glommio_::Result<ExecutorJoinHandle<Result<T>>, ()>
+    where
+        G: FnOnce() -> F + Send + 'static,
+        F: Future<Output = T> + 'static,
+        T: Send + 'static;
+}
+impl LocalExecutorBuilderExt for LocalExecutorBuilder {
+    fn spawn_with_priority<G, F, T>(
+        self,
+        priority: Priority,
+        fut_gen: G,
+    ) -> glommio_::Result<ExecutorJoinHandle<Result<T>>, ()>
+    where
+        G: FnOnce() -> F + Send + 'static,
+        F: Future<Output = T> + 'static,
+        T: Send + 'static,
+    {
+        self.spawn(move || run_prioritized(set_current_priority(priority).map(|()| fut_gen())))
+    }
+}
+
+/// An extension trait for [`glommio::LocalExecutorPoolBuilder`](LocalExecutorPoolBuilder), that
+/// allows spawning pools of executors with a specific I/O priority.
+pub trait LocalExecutorPoolBuilderExt: private::Sealed {
+    /// Spawn an executor on every shard of the pool, like
+    /// [`on_all_shards`](LocalExecutorPoolBuilder::on_all_shards), but set the I/O priority of
+    /// each executor thread before generating its future, in the same way as
+    /// [`LocalExecutorBuilderExt::spawn_with_priority`].
+    fn on_all_shards_with_priority<G, F, T>(
+        self,
+        priority: Priority,
+        fut_gen: G,
+    ) -> glommio_::Result<PoolThreadHandles<Result<T>>, ()>
+    where
+        G: FnOnce() -> F + Clone + Send + 'static,
+        F: Future<Output = T> + 'static,
+        T: Send + 'static;
+}
+impl LocalExecutorPoolBuilderExt for LocalExecutorPoolBuilder {
+    fn on_all_shards_with_priority<G, F, T>(
+        self,
+        priority: Priority,
+        fut_gen: G,
+    ) -> glommio_::Result<PoolThreadHandles<Result<T>>, ()>
+    where
+        G: FnOnce() -> F + Clone + Send + 'static,
+        F: Future<Output = T> + 'static,
+        T: Send + 'static,
+    {
+        self.on_all_shards(move || {
+            run_prioritized(set_current_priority(priority).map(|()| fut_gen()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glommio_::{LocalExecutorBuilder, Placement, PoolPlacement};
+
+    #[test]
+    fn executors_run_at_the_priority() {
+        let before = get_current_priority().unwrap();
+        let spawned = LocalExecutorBuilder::new(Placement::Unbound)
+            .spawn_with_priority(Priority::idle(), || async { get_current_priority() });
+
+        // Glommio needs io_uring, and enough locked memory for its buffers, neither of which
+        // every environment provides.
+        let priority = match spawned.map(ExecutorJoinHandle::join) {
+            Ok(Ok(priority)) => priority,
+            Ok(Err(error)) | Err(error) => {
+                eprintln!("skipping, since glommio cannot run here: {}", error);
+                return;
+            }
+        };
+        assert_eq!(priority, Ok(Ok(Priority::idle())));
+        assert_eq!(get_current_priority(), Ok(before));
+
+        let handles = LocalExecutorPoolBuilder::new(PoolPlacement::Unbound(2))
+            .on_all_shards_with_priority(Priority::idle(), || async { get_current_priority() })
+            .unwrap();
+
+        for result in handles.join_all() {
+            assert_eq!(result.unwrap(), Ok(Ok(Priority::idle())));
+        }
+    }
+}
@@ -12,25 +12,115 @@
 //! use, which is the default I/O scheduler.
 //!
 //! Refer to the _ioprio_set(2)_ syscall man page for more information about these API:s.
+//!
+//! Everything that issues syscalls or reads `/proc` and `/sys` requires the `std` feature, which
+//! is enabled by default. Without it, the crate is `no_std`, and only provides the types for
+//! building and decoding priority masks, such as [`Priority`] and [`Class`], which can then be
+//! passed to the kernel by other means.
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(missing_docs)]
-use std::cmp::Ordering;
-use std::convert::TryInto;
+use core::cmp::Ordering;
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::thread::{self, JoinHandle};
 
+#[cfg(feature = "std")]
 use nix::errno::Errno;
+#[cfg(feature = "std")]
+use nix::unistd::{geteuid, getpgrp, getpid, gettid, getuid};
+#[cfg(feature = "std")]
 pub use nix::unistd::{Pid, Uid};
 
+#[cfg(feature = "std")]
+mod cgroup;
+#[cfg(feature = "std")]
+mod command;
+#[cfg(feature = "glommio")]
+mod glommio;
+#[cfg(feature = "async")]
+mod offload;
+#[cfg(feature = "std")]
+mod parse;
+#[cfg(feature = "std")]
+mod procfs;
+#[cfg(feature = "proptest")]
+mod proptest;
+#[cfg(feature = "rayon")]
+mod rayon;
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "std")]
+mod sysfs;
+#[cfg(feature = "tokio-uring")]
+mod tokio_uring;
+#[cfg(feature = "std")]
+mod watch;
+
+#[cfg(feature = "std")]
+pub use cgroup::{combined_effective_priority, set_frozen_group_priority, CombinedPriority};
+#[cfg(feature = "std")]
+pub use command::CommandExt;
+#[cfg(feature = "glommio")]
+pub use glommio::{LocalExecutorBuilderExt, LocalExecutorPoolBuilderExt};
+#[cfg(feature = "async")]
+pub use offload::{get_priority_async, set_priority_async, Offloaded};
+#[cfg(feature = "std")]
+pub use parse::{ParseError, ENV_VAR};
+#[cfg(feature = "rayon")]
+pub use rayon::ThreadPoolBuilderExt;
+#[cfg(feature = "std")]
+pub use sysfs::{
+    fd_scheduler_honors_ioprio, ioprio_effective_for_fd, list_block_devices, read_iosched_tunables,
+    scheduler_honors_ioprio, BlockDevice, IoschedTunables,
+};
+#[cfg(feature = "tokio-uring")]
+pub use tokio_uring::{
+    read_at_with_priority, write_at_with_priority, PrioritizedOpData, PrioritizedRead,
+    PrioritizedReadTransform, PrioritizedWrite, PrioritizedWriteTransform,
+};
+#[cfg(feature = "std")]
+pub use watch::{watch_priority, PriorityWatcher};
+
 /// An I/O priority, either associated with a class and per-class data, or the standard priority.
+///
+/// Priorities are totally ordered by their class first, and then by their level within the class,
+/// so that for example best-effort level 0 is greater than best-effort level 7, which is in turn
+/// greater than idle. The [standard](Priority::standard) priority, of the [`None`](Class::None)
+/// class, is placed between the idle and best-effort classes: it resolves to a best-effort level
+/// derived from the nice value, which can only be known for a specific process, but is never idle.
+/// Masks that cannot be decoded are less than every valid priority, and masks that are equal in
+/// class and level but encoded differently, such as the idle class with nonzero data, are ordered
+/// by their raw mask. This order can be used for sorting and as keys in ordered collections, but
+/// unlike [`sort_key`](Priority::sort_key), it does not treat the standard priority as equivalent
+/// to best-effort level 4.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct Priority {
     inner: u16,
 }
+impl Ord for Priority {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let by_class = match (self.class(), other.class()) {
+            (Some(lhs), Some(rhs)) => Ord::cmp(&lhs, &rhs),
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => Ordering::Equal,
+        };
+        by_class.then_with(|| Ord::cmp(&self.inner, &other.inner))
+    }
+}
 impl PartialOrd for Priority {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(Ord::cmp(&self.class()?, &other.class()?))
+        Some(Ord::cmp(self, other))
     }
 }
 
 /// A target, consisting of one or more processes matching the given query.
+#[cfg(feature = "std")]
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Target {
     /// A single process. Note that a PID value of zero refers to the calling process.
@@ -41,10 +131,18 @@ pub enum Target {
     ProcessGroup(Pid),
     /// All processes owned by a user. (`IOPRIO_WHO_USER`.)
     User(Uid),
+    /// A single thread, identified by its TID. As with processes, a TID of zero refers to the
+    /// calling thread. (`IOPRIO_WHO_PROCESS`.)
+    ///
+    /// I/O priorities belong to individual threads, and the kernel looks up the `who` argument of
+    /// `IOPRIO_WHO_PROCESS` in the same namespace for PIDs and TIDs, since the PID of a process is
+    /// simply the TID of its main thread. This is thus equivalent to [`Target::Process`], which
+    /// likewise only ever affects the main thread of a process, but makes the intent explicit.
+    Thread(Pid),
 }
 
 /// A priority class, being either real-time (`IOPRIO_CLASS_RT`), best-effort (`IOPRIO_CLASS_BE`),
-/// or idle (`IOPRIO_CLASS_IDLE`).
+/// idle (`IOPRIO_CLASS_IDLE`), or none (`IOPRIO_CLASS_NONE`).
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Class {
     /// The real-time class (`IOPRIO_CLASS_RT`), requiring elevated privileges to set to.
@@ -57,6 +155,16 @@ pub enum Class {
     /// completely idle. This is the lowest possible priority, and does not require any capability
     /// to set (with the exception of kernels before 2.6.25).
     Idle,
+    /// No class (`IOPRIO_CLASS_NONE`), which is what the [standard](Priority::standard) priority
+    /// encodes.
+    ///
+    /// Threads without any class set are scheduled as best-effort, at a level derived from their
+    /// CPU nice value, `(nice + 20) / 5`, so that the default nice value of 0 corresponds to level
+    /// 4. Threads using a real-time CPU scheduling policy are instead scheduled in the real-time
+    /// class, and those using `SCHED_IDLE` in the idle class. Some kernels report threads that
+    /// have never been assigned a priority as having no class, but level 4, which also decodes
+    /// into this class.
+    None,
 }
 
 /// Real-time I/O priority levels, ranging from the numerical values 0-7, but reversed.
@@ -94,8 +202,32 @@ impl RtPriorityLevel {
     pub const fn level(self) -> u8 {
         self.inner
     }
-    fn data(self) -> u16 {
-        self.inner.into()
+    /// Move one level towards the highest priority, saturating at [`highest`](Self::highest).
+    pub const fn raise(self) -> Self {
+        self.raise_by(1)
+    }
+    /// Move one level towards the lowest priority, saturating at [`lowest`](Self::lowest).
+    pub const fn lower(self) -> Self {
+        self.lower_by(1)
+    }
+    /// Move a number of levels towards the highest priority, that is towards level 0, saturating
+    /// at [`highest`](Self::highest).
+    pub const fn raise_by(self, steps: u8) -> Self {
+        Self {
+            inner: self.inner.saturating_sub(steps),
+        }
+    }
+    /// Move a number of levels towards the lowest priority, that is towards level 7, saturating at
+    /// [`lowest`](Self::lowest).
+    pub const fn lower_by(self, steps: u8) -> Self {
+        let inner = self.inner.saturating_add(steps);
+
+        Self {
+            inner: if inner > 7 { 7 } else { inner },
+        }
+    }
+    const fn data(self) -> u16 {
+        self.inner as u16
     }
 }
 impl Ord for RtPriorityLevel {
@@ -140,8 +272,32 @@ impl BePriorityLevel {
     pub const fn level(self) -> u8 {
         self.inner
     }
-    fn data(self) -> u16 {
-        self.inner.into()
+    /// Move one level towards the highest priority, saturating at [`highest`](Self::highest).
+    pub const fn raise(self) -> Self {
+        self.raise_by(1)
+    }
+    /// Move one level towards the lowest priority, saturating at [`lowest`](Self::lowest).
+    pub const fn lower(self) -> Self {
+        self.lower_by(1)
+    }
+    /// Move a number of levels towards the highest priority, that is towards level 0, saturating
+    /// at [`highest`](Self::highest).
+    pub const fn raise_by(self, steps: u8) -> Self {
+        Self {
+            inner: self.inner.saturating_sub(steps),
+        }
+    }
+    /// Move a number of levels towards the lowest priority, that is towards level 7, saturating at
+    /// [`lowest`](Self::lowest).
+    pub const fn lower_by(self, steps: u8) -> Self {
+        let inner = self.inner.saturating_add(steps);
+
+        Self {
+            inner: if inner > 7 { 7 } else { inner },
+        }
+    }
+    const fn data(self) -> u16 {
+        self.inner as u16
     }
 }
 impl Ord for BePriorityLevel {
@@ -155,26 +311,280 @@ impl PartialOrd for BePriorityLevel {
     }
 }
 
+mod private {
+    pub trait Sealed {}
+}
+impl private::Sealed for RtPriorityLevel {}
+impl private::Sealed for BePriorityLevel {}
+
+/// A trait implemented by both [`RtPriorityLevel`] and [`BePriorityLevel`], allowing code to be
+/// generic over the level type.
+///
+/// Levels of both types range from 0 to 7, where 0 is the highest priority, and are ordered by
+/// priority rather than numerically. This trait is sealed, and cannot be implemented outside of
+/// this crate.
+pub trait PriorityLevel: private::Sealed + Copy + Ord + fmt::Debug {
+    /// The highest level, 0.
+    fn highest() -> Self;
+    /// The lowest level, 7.
+    fn lowest() -> Self;
+    /// Wrap an underlying level, returning [`None`] if it exceeds 7.
+    fn from_level(level: u8) -> Option<Self>;
+    /// Get the underlying level, ranging from 0 to 7.
+    fn level(self) -> u8;
+}
+impl PriorityLevel for RtPriorityLevel {
+    fn highest() -> Self {
+        Self::highest()
+    }
+    fn lowest() -> Self {
+        Self::lowest()
+    }
+    fn from_level(level: u8) -> Option<Self> {
+        Self::from_level(level)
+    }
+    fn level(self) -> u8 {
+        self.level()
+    }
+}
+impl PriorityLevel for BePriorityLevel {
+    fn highest() -> Self {
+        Self::highest()
+    }
+    fn lowest() -> Self {
+        Self::lowest()
+    }
+    fn from_level(level: u8) -> Option<Self> {
+        Self::from_level(level)
+    }
+    fn level(self) -> u8 {
+        self.level()
+    }
+}
+
+impl TryFrom<u8> for RtPriorityLevel {
+    type Error = LevelOutOfRange;
+
+    /// Wrap an underlying level, like [`from_level`](Self::from_level), but failing with an error
+    /// if it exceeds 7.
+    fn try_from(level: u8) -> Result<Self, Self::Error> {
+        Self::from_level(level).ok_or(LevelOutOfRange { level })
+    }
+}
+impl TryFrom<u8> for BePriorityLevel {
+    type Error = LevelOutOfRange;
+
+    /// Wrap an underlying level, like [`from_level`](Self::from_level), but failing with an error
+    /// if it exceeds 7.
+    fn try_from(level: u8) -> Result<Self, Self::Error> {
+        Self::from_level(level).ok_or(LevelOutOfRange { level })
+    }
+}
+
+/// The error returned when trying to convert a level greater than 7 into a priority level.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct LevelOutOfRange {
+    level: u8,
+}
+impl LevelOutOfRange {
+    /// Get the level that failed to convert.
+    pub const fn level(self) -> u8 {
+        self.level
+    }
+}
+impl fmt::Display for LevelOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "I/O priority level {} is not in 0-7", self.level)
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for LevelOutOfRange {}
+
+/// Construct a [`BePriorityLevel`] from a constant level, failing to compile if the level exceeds
+/// 7.
+///
+/// ```
+/// use ioprio::{be_level, BePriorityLevel};
+///
+/// const LEVEL: BePriorityLevel = be_level!(6);
+/// assert_eq!(LEVEL.level(), 6);
+/// ```
+///
+/// ```compile_fail
+/// let level = ioprio::be_level!(8);
+/// ```
+#[macro_export]
+macro_rules! be_level {
+    ($level:expr) => {{
+        const LEVEL: $crate::BePriorityLevel = match $crate::BePriorityLevel::from_level($level) {
+            ::core::option::Option::Some(level) => level,
+            ::core::option::Option::None => panic!("best-effort priority level is not in 0-7"),
+        };
+        LEVEL
+    }};
+}
+/// Construct an [`RtPriorityLevel`] from a constant level, failing to compile if the level exceeds
+/// 7.
+///
+/// ```
+/// use ioprio::{rt_level, RtPriorityLevel};
+///
+/// const LEVEL: RtPriorityLevel = rt_level!(0);
+/// assert_eq!(LEVEL, RtPriorityLevel::highest());
+/// ```
+///
+/// ```compile_fail
+/// let level = ioprio::rt_level!(8);
+/// ```
+#[macro_export]
+macro_rules! rt_level {
+    ($level:expr) => {{
+        const LEVEL: $crate::RtPriorityLevel = match $crate::RtPriorityLevel::from_level($level) {
+            ::core::option::Option::Some(level) => level,
+            ::core::option::Option::None => panic!("real-time priority level is not in 0-7"),
+        };
+        LEVEL
+    }};
+}
+
+/// The error returned by [`Priority::from_ionice`], when the numeric class and level do not form a
+/// valid priority.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct InvalidIoniceArgs {
+    class: u8,
+    level: u8,
+}
+impl InvalidIoniceArgs {
+    /// Get the numeric class that failed to convert.
+    pub const fn class(self) -> u8 {
+        self.class
+    }
+    /// Get the level that failed to convert.
+    pub const fn level(self) -> u8 {
+        self.level
+    }
+}
+impl fmt::Display for InvalidIoniceArgs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.class {
+            0..=3 => write!(
+                f,
+                "invalid level {} for the I/O priority class {}",
+                self.level, self.class
+            ),
+            _ => write!(f, "invalid I/O priority class {}", self.class),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidIoniceArgs {}
+
 impl Class {
+    /// The class and level with the highest priority, real-time level 0.
+    pub const fn highest() -> Self {
+        Self::Realtime(RtPriorityLevel::highest())
+    }
+    /// The class with the lowest priority, idle.
+    pub const fn lowest() -> Self {
+        Self::Idle
+    }
+    /// Get a short code identifying the class, regardless of its level: `rt`, `be`, `idle` or
+    /// `none`.
+    ///
+    /// Unlike the [`Display`](fmt::Display) implementation, these codes are guaranteed to remain
+    /// stable, making them suitable for metrics labels, structured logs, and wire formats.
+    pub const fn as_code(self) -> &'static str {
+        match self {
+            Self::Realtime(_) => "rt",
+            Self::BestEffort(_) => "be",
+            Self::Idle => "idle",
+            Self::None => "none",
+        }
+    }
+    /// Parse a code as returned by [`as_code`](Self::as_code), returning [`None`] if it is not one
+    /// of the four codes.
+    ///
+    /// Since the codes do not carry any level, `rt` and `be` both map to level 4, the level used
+    /// by the kernel for processes with the default nice value.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "rt" => Some(Self::Realtime(RtPriorityLevel { inner: 4 })),
+            "be" => Some(Self::BestEffort(BePriorityLevel::fallback())),
+            "idle" => Some(Self::Idle),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+    /// Iterate over every class and level that can be selected, along with a label suitable for
+    /// display, such as `Best-effort, level 4`.
+    ///
+    /// The classes are ordered from the highest to the lowest priority, that is real-time levels 0
+    /// to 7, followed by best-effort levels 0 to 7, and finally idle, for a total of 17 items. The
+    /// [`None`](Class::None) class is not included, as it does not represent a choice of level but
+    /// rather the absence of one.
+    pub fn variants_with_levels() -> impl Iterator<Item = (Class, &'static str)> {
+        const RT_LABELS: [&str; 8] = [
+            "Real-time, level 0",
+            "Real-time, level 1",
+            "Real-time, level 2",
+            "Real-time, level 3",
+            "Real-time, level 4",
+            "Real-time, level 5",
+            "Real-time, level 6",
+            "Real-time, level 7",
+        ];
+        const BE_LABELS: [&str; 8] = [
+            "Best-effort, level 0",
+            "Best-effort, level 1",
+            "Best-effort, level 2",
+            "Best-effort, level 3",
+            "Best-effort, level 4",
+            "Best-effort, level 5",
+            "Best-effort, level 6",
+            "Best-effort, level 7",
+        ];
+
+        let realtime = (0..8).map(|level| {
+            let class = Class::Realtime(RtPriorityLevel { inner: level });
+            (class, RT_LABELS[usize::from(level)])
+        });
+        let best_effort = (0..8).map(|level| {
+            let class = Class::BestEffort(BePriorityLevel { inner: level });
+            (class, BE_LABELS[usize::from(level)])
+        });
+
+        realtime
+            .chain(best_effort)
+            .chain(core::iter::once((Class::Idle, "Idle")))
+    }
     fn rel_priority(self) -> u8 {
         match self {
-            Self::Realtime(_) => 2,
-            Self::BestEffort(_) => 1,
+            Self::Realtime(_) => 3,
+            Self::BestEffort(_) => 2,
+            Self::None => 1,
             Self::Idle => 0,
         }
     }
-    fn kind(self) -> u16 {
+    /// Get the raw class, as stored in bits 13-15 of a mask: 0 for none, 1 for real-time, 2 for
+    /// best-effort, and 3 for idle.
+    ///
+    /// Together with [`raw_data`](Self::raw_data), this can be passed to
+    /// [`Priority::prio_value`] to build the mask of the class.
+    pub const fn raw_kind(self) -> u16 {
         match self {
+            Self::None => 0,
             Self::Realtime(_) => 1,
             Self::BestEffort(_) => 2,
             Self::Idle => 3,
         }
     }
-    fn data(self) -> u16 {
+    /// Get the raw per-class data, as stored in bits 0-12 of a mask, which is the level for the
+    /// real-time and best-effort classes, and 0 for the other classes.
+    pub const fn raw_data(self) -> u16 {
         match self {
             Self::Realtime(rt) => rt.data(),
             Self::BestEffort(be) => be.data(),
-            Self::Idle => 0,
+            Self::Idle | Self::None => 0,
         }
     }
 }
@@ -183,7 +593,7 @@ impl Ord for Class {
         Ord::cmp(&self.rel_priority(), &other.rel_priority()).then_with(|| match (self, other) {
             (Self::Realtime(lhs), Self::Realtime(rhs)) => Ord::cmp(&lhs, &rhs),
             (Self::BestEffort(lhs), Self::BestEffort(rhs)) => Ord::cmp(&lhs, &rhs),
-            (Self::Idle, Self::Idle) => Ordering::Equal,
+            (Self::Idle, Self::Idle) | (Self::None, Self::None) => Ordering::Equal,
 
             _ => unreachable!(),
         })
@@ -194,20 +604,54 @@ impl PartialOrd for Class {
         Some(Ord::cmp(self, other))
     }
 }
+/// Formats the class the same way as `ionice` does, that is `realtime: prio 0`, `best-effort: prio
+/// 4`, `idle`, or `none`.
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Realtime(rt) => write!(f, "realtime: prio {}", rt.level()),
+            Self::BestEffort(be) => write!(f, "best-effort: prio {}", be.level()),
+            Self::Idle => f.write_str("idle"),
+            Self::None => f.write_str("none"),
+        }
+    }
+}
 
 impl Priority {
+    /// The lowest valid priority, the idle class.
+    ///
+    /// Every valid priority is greater than or equal to this, whereas masks that cannot be decoded
+    /// are less.
+    pub const MIN: Self = Self::new(Class::lowest());
+    /// The highest priority, real-time level 0.
+    pub const MAX: Self = Self::new(Class::highest());
+
     /// Construct a new I/O priority value, from the priority class and per-class level.
-    pub fn new(class: Class) -> Self {
+    ///
+    /// This can also be used in constant expressions:
+    ///
+    /// ```
+    /// use ioprio::{Class, Priority};
+    ///
+    /// const DEFAULT: Priority = Priority::new(Class::Idle);
+    ///
+    /// assert_eq!(DEFAULT, Priority::background());
+    /// ```
+    pub const fn new(class: Class) -> Self {
         Self {
-            inner: ((class.kind() as u16) << 13) | class.data(),
+            inner: Self::prio_value(class.raw_kind(), class.raw_data()),
         }
     }
-    /// Retrieve the class, if any such class was set.
+    /// Retrieve the class, or [`None`] if the mask cannot be decoded.
+    ///
+    /// The [standard](Self::standard) priority decodes into [`Class::None`], as does a mask without
+    /// a class but with a level from 0 to 7.
     pub fn class(self) -> Option<Class> {
-        let class_raw = self.inner >> 13;
-        let data = self.inner & 0x1FFF;
+        let class_raw = Self::raw_class(self.inner);
+        let data = Self::raw_data(self.inner);
 
         Some(match class_raw {
+            0 if data < 8 => Class::None,
             1 => Class::Realtime(RtPriorityLevel::from_level(data.try_into().ok()?)?),
             2 => Class::BestEffort(BePriorityLevel::from_level(data.try_into().ok()?)?),
             3 => Class::Idle,
@@ -215,10 +659,318 @@ impl Priority {
             _ => return None,
         })
     }
+    /// Decode the priority, distinguishing the standard priority from masks that cannot be decoded.
+    ///
+    /// This returns [`Standard`](DecodedPriority::Standard) for every mask of the
+    /// [`None`](Class::None) class, and keeps the raw mask of those that cannot be decoded in
+    /// [`Unknown`](DecodedPriority::Unknown), which is useful for logging or passing the value
+    /// through. Every other mask for which [`class`](Self::class) returns a class is
+    /// [`Known`](DecodedPriority::Known).
+    pub fn decoded(self) -> DecodedPriority {
+        match self.class() {
+            Some(Class::None) => DecodedPriority::Standard,
+            Some(class) => DecodedPriority::Known(class),
+            None => DecodedPriority::Unknown(self.inner),
+        }
+    }
     /// Get the value for the default priority, with the inner value of zero.
     pub const fn standard() -> Self {
         Self { inner: 0 }
     }
+    /// Construct a priority of the [`Realtime`](Class::Realtime) class, at the given level.
+    ///
+    /// ```
+    /// use ioprio::{Class, Priority, RtPriorityLevel};
+    ///
+    /// let level = RtPriorityLevel::from_level(2).unwrap();
+    /// assert_eq!(Priority::realtime(2).unwrap().class(), Some(Class::Realtime(level)));
+    ///
+    /// assert_eq!(Priority::realtime(8).unwrap_err().level(), 8);
+    /// ```
+    pub fn realtime(level: u8) -> Result<Self, LevelOutOfRange> {
+        RtPriorityLevel::try_from(level).map(|level| Self::new(Class::Realtime(level)))
+    }
+    /// Construct a priority of the [`BestEffort`](Class::BestEffort) class, at the given level.
+    pub fn best_effort(level: u8) -> Result<Self, LevelOutOfRange> {
+        BePriorityLevel::try_from(level).map(|level| Self::new(Class::BestEffort(level)))
+    }
+    /// Construct a priority of the [`Idle`](Class::Idle) class, which has no levels.
+    pub const fn idle() -> Self {
+        Self::new(Class::Idle)
+    }
+    /// Iterate over every valid priority, in the same order as [`ALL_PRIORITIES`].
+    ///
+    /// Every priority yielded decodes into a [`Class`], including the [standard](Self::standard)
+    /// priority which decodes into [`Class::None`], and constructing a priority from that class
+    /// again yields the same mask.
+    pub fn all() -> impl ExactSizeIterator<Item = Priority> + DoubleEndedIterator {
+        ALL_PRIORITIES.iter().copied()
+    }
+    const fn from_class_data(class: u16, data: u16) -> Self {
+        Self {
+            inner: Self::prio_value(class, data),
+        }
+    }
+    /// The position on the scale of effective urgency, from idle (0), through best-effort levels 7
+    /// to 0 (1-8), to real-time levels 7 to 0 (9-16). The standard priority is treated as
+    /// best-effort level 4, and invalid masks have no rank.
+    fn effective_rank(self) -> Option<u8> {
+        Some(match self.class()? {
+            Class::None => 8 - BePriorityLevel::fallback().level(),
+            Class::Idle => 0,
+            Class::BestEffort(be) => 8 - be.level(),
+            Class::Realtime(rt) => 16 - rt.level(),
+        })
+    }
+    /// The inverse of [`effective_rank`](Self::effective_rank), for ranks up to 16.
+    fn from_effective_rank(rank: u8) -> Self {
+        Self::new(match rank {
+            0 => Class::Idle,
+            1..=8 => Class::BestEffort(BePriorityLevel { inner: 8 - rank }),
+            _ => Class::Realtime(RtPriorityLevel { inner: 16 - rank }),
+        })
+    }
+    /// Get the next valid priority that is less urgent than this one, in the total order of
+    /// [`Priority`], or [`None`] if this is idle.
+    ///
+    /// This crosses class boundaries, so that the step below real-time level 7 is best-effort level
+    /// 0, and the steps below best-effort level 7 are the [standard](Self::standard) priority and
+    /// then idle. Masks that are not [valid](Self::is_valid) have no next priority.
+    pub fn next_less_urgent(self) -> Option<Self> {
+        if !Self::is_valid(self.inner) {
+            return None;
+        }
+        Self::all().filter(|&other| other < self).max()
+    }
+    /// Get the next valid priority that is more urgent than this one, in the total order of
+    /// [`Priority`], or [`None`] if this is real-time level 0.
+    ///
+    /// This is the inverse of [`next_less_urgent`](Self::next_less_urgent).
+    pub fn next_more_urgent(self) -> Option<Self> {
+        if !Self::is_valid(self.inner) {
+            return None;
+        }
+        Self::all().filter(|&other| other > self).min()
+    }
+    /// Resolve the [`None`](Class::None) class into the best-effort level that the kernel derives
+    /// from a CPU nice value, leaving every other priority unchanged.
+    ///
+    /// The level is `(nice + 20) / 5`, clamped to the range 0-7, so that nice values from -20 to
+    /// 19 map to levels 0 to 7, with the default nice value of 0 yielding level 4. Note that the
+    /// kernel additionally schedules threads with a real-time or idle CPU scheduling policy in the
+    /// corresponding I/O class, which is not taken into account here.
+    pub fn effective(self, nice: i32) -> Self {
+        match self.class() {
+            Some(Class::None) => {
+                let level = (nice.saturating_add(20) / 5).clamp(0, 7);
+                Self::new(Class::BestEffort(BePriorityLevel { inner: level as u8 }))
+            }
+            _ => self,
+        }
+    }
+    /// Compare the urgency of two priorities, after resolving either of them of the
+    /// [`None`](Class::None) class using the nice value of its respective thread.
+    ///
+    /// Both priorities are first resolved using [`effective`](Self::effective), and then compared
+    /// using [`Ord`]. Unlike [`Ord`], which always ranks the standard priority between idle and
+    /// best-effort, this accounts for the standard priority of a thread with a low nice value
+    /// being more urgent than an explicit best-effort level of another thread.
+    pub fn cmp_effective(self, other: Self, self_nice: i32, other_nice: i32) -> Ordering {
+        Ord::cmp(&self.effective(self_nice), &other.effective(other_nice))
+    }
+    /// Shift this priority by a number of steps on the scale of effective urgency, where positive
+    /// steps are more urgent, and negative steps less urgent.
+    ///
+    /// The scale ranges from idle, through best-effort levels 7 to 0, to real-time levels 7 to 0,
+    /// with each step being one level, and crossing class boundaries where necessary. Thus,
+    /// shifting best-effort level 7 by -1 yields idle, and shifting best-effort level 0 by 1 yields
+    /// real-time level 7. The result is clamped to idle and real-time level 0 at the extremes.
+    ///
+    /// The [standard](Self::standard) priority is shifted as if it were best-effort level 4. An
+    /// offset of zero, as well as any offset applied to a mask without a class, leaves the
+    /// priority unchanged. This is purely a computation, and does not change the priority of
+    /// anything by itself.
+    pub fn with_offset(self, steps: i32) -> Self {
+        match self.effective_rank() {
+            Some(rank) if steps != 0 => {
+                let shifted = i32::from(rank).saturating_add(steps).clamp(0, 16);
+                Self::from_effective_rank(shifted as u8)
+            }
+            _ => self,
+        }
+    }
+    /// Construct a priority from the numeric class and level used by `ionice -c <class> -n
+    /// <level>`.
+    ///
+    /// The class is 0 for none (the [standard](Self::standard) priority), 1 for real-time, 2 for
+    /// best-effort, and 3 for idle, and the level ranges from 0 to 7. Unlike `ionice`, which
+    /// ignores the level of the idle and none classes, a nonzero level is rejected for them. Pass a
+    /// level of 0 where `-n` was not given for those classes, and 4 (the `ionice` default) for the
+    /// others.
+    pub fn from_ionice(class: u8, level: u8) -> Result<Self, InvalidIoniceArgs> {
+        let error = InvalidIoniceArgs { class, level };
+
+        if class > 3 || level > 7 {
+            return Err(error);
+        }
+        Self::try_from(Self::prio_value(class.into(), level.into())).map_err(|_| error)
+    }
+    /// Get the numeric class and level of this priority, as used by `ionice -c <class> -n
+    /// <level>`.
+    ///
+    /// This is the inverse of [`from_ionice`](Self::from_ionice), where the level of the idle and
+    /// none classes is 0. Masks that cannot be decoded yield their raw class, and their data
+    /// truncated to 8 bits.
+    pub const fn to_ionice(self) -> (u8, u8) {
+        (
+            Self::raw_class(self.inner) as u8,
+            Self::raw_data(self.inner) as u8,
+        )
+    }
+    /// Express this priority as arguments to the `ionice` command, such as `["-c", "2", "-n",
+    /// "4"]`.
+    ///
+    /// `ionice` uses the same numeric classes as the kernel: 0 for none (the
+    /// [standard](Self::standard) priority), 1 for real-time, 2 for best-effort, and 3 for idle,
+    /// which is given by `-c`. The level is given by `-n`, and is omitted for the idle class and
+    /// the standard priority. Masks that do not decode into a class are passed through
+    /// numerically, which `ionice` will then reject.
+    #[cfg(feature = "std")]
+    pub fn to_ionice_args(&self) -> Vec<String> {
+        let class_raw = Self::raw_class(self.inner);
+        let data = Self::raw_data(self.inner);
+
+        let mut args = vec!["-c".to_owned(), class_raw.to_string()];
+
+        if class_raw == 1 || class_raw == 2 || data != 0 {
+            args.push("-n".to_owned());
+            args.push(data.to_string());
+        }
+        args
+    }
+    /// Map a percentage onto the scale of effective urgency, where 0% is idle, and 100% is either
+    /// real-time level 0, or best-effort level 0 if `include_realtime` is false.
+    ///
+    /// The percentage is divided evenly across the levels in between, rounding to the nearest one,
+    /// and anything above 100% is treated as 100%. Excluding the real-time class is useful for
+    /// interfaces used by unprivileged users, who cannot set real-time priorities anyway.
+    ///
+    /// Since the scale only has 17 levels, or 9 without the real-time class, percentages are
+    /// quantized to the nearest level, and only the percentages returned by
+    /// [`to_percent`](Self::to_percent) survive a round trip. For example, 33% is best-effort
+    /// level 3, which maps back to 31%.
+    pub fn from_percent(percent: u8, include_realtime: bool) -> Self {
+        let max = if include_realtime { 16 } else { 8 };
+        let percent = u16::from(percent.min(100));
+
+        Self::from_effective_rank(((percent * max + 50) / 100) as u8)
+    }
+    /// Map this priority back to a percentage, using the same scale as
+    /// [`from_percent`](Self::from_percent).
+    ///
+    /// The [standard](Self::standard) priority is treated as best-effort level 4, and real-time
+    /// priorities are reported as 100% if `include_realtime` is false. Masks without a class have
+    /// no percentage.
+    pub fn to_percent(&self, include_realtime: bool) -> Option<u8> {
+        let max = if include_realtime { 16 } else { 8 };
+        let rank = u16::from(self.effective_rank()?).min(max);
+
+        Some(((rank * 100 + max / 2) / max) as u8)
+    }
+    /// Get a key for sorting priorities by their effective urgency, in ascending order.
+    ///
+    /// Masks that cannot be decoded have the key 0, followed by idle (1), best-effort levels 7 to
+    /// 0 (2-9), and real-time levels 7 to 0 (10-17). The [standard](Self::standard) priority has
+    /// the same key as best-effort level 4.
+    pub fn sort_key(&self) -> u8 {
+        self.effective_rank().map_or(0, |rank| rank + 1)
+    }
+    /// Get a key for sorting priorities by their effective urgency, like
+    /// [`sort_key`](Self::sort_key), but which is unique for every mask.
+    ///
+    /// The key consists of the `sort_key` in the upper 16 bits, and the raw mask in the lower 16
+    /// bits, which breaks ties between priorities that are equally urgent but encoded differently.
+    /// Sorting by this key is thus deterministic, even with unstable sorts.
+    pub fn total_sort_key(&self) -> u32 {
+        (u32::from(self.sort_key()) << 16) | u32::from(self.inner)
+    }
+    /// Merge two priorities conservatively, returning the one with the lower effective urgency.
+    ///
+    /// This is the opposite of taking the maximum, which is what the kernel reports for multiple
+    /// processes: when in doubt, the less urgent priority wins, being less likely to starve other
+    /// I/O or to require privileges. The priorities are compared by the same scale as
+    /// [`with_offset`](Self::with_offset), where the [standard](Self::standard) priority counts as
+    /// best-effort level 4.
+    ///
+    /// If exactly one of the priorities cannot be decoded, the other one is returned, since nothing
+    /// can be said about the urgency of an unknown mask. If both are equally urgent, or if neither
+    /// can be decoded, `a` is returned.
+    pub fn conservative_merge(a: Self, b: Self) -> Self {
+        match (a.effective_rank(), b.effective_rank()) {
+            (Some(lhs), Some(rhs)) if rhs < lhs => b,
+            (None, Some(_)) => b,
+            _ => a,
+        }
+    }
+    /// Describe this priority as a full sentence, suitable for accessible interfaces, such as
+    /// "Real-time I/O priority, level 2 of 8 (0 is highest); this can delay other programs' disk
+    /// access."
+    #[cfg(feature = "std")]
+    pub fn to_sentence(&self) -> String {
+        match self.decoded() {
+            DecodedPriority::Known(Class::Realtime(rt)) => format!(
+                "Real-time I/O priority, level {} of 8 (0 is highest); this can delay other \
+                 programs' disk access.",
+                rt.level()
+            ),
+            DecodedPriority::Known(Class::BestEffort(be)) => format!(
+                "Best-effort I/O priority, level {} of 8 (0 is highest); disk access is shared \
+                 with other programs according to their levels.",
+                be.level()
+            ),
+            DecodedPriority::Known(Class::Idle) => {
+                "Idle I/O priority; this runs only when the disk is otherwise idle.".to_owned()
+            }
+            DecodedPriority::Standard | DecodedPriority::Known(Class::None) => {
+                let sentence = "Standard I/O priority; this is best-effort, with the level \
+                                derived from the program's CPU nice value.";
+                sentence.to_owned()
+            }
+            DecodedPriority::Unknown(mask) => {
+                format!("Unknown I/O priority, with the mask {:#06x}.", mask)
+            }
+        }
+    }
+    /// Check whether this priority risks starving the I/O of the rest of the system, which is the
+    /// case for every [real-time](Class::Realtime) priority.
+    ///
+    /// The real-time class is granted timeslices regardless of any other I/O, and can thus block
+    /// all best-effort and idle I/O for as long as it keeps the device busy. This can be used to
+    /// warn the user before applying such a priority.
+    pub fn is_starvation_risk(&self) -> bool {
+        matches!(self.class(), Some(Class::Realtime(_)))
+    }
+    /// Get the capabilities required to set this priority, regardless of the target.
+    ///
+    /// Only the [real-time](Class::Realtime) class requires a capability, namely `CAP_SYS_ADMIN`,
+    /// although Linux 5.11 and later also accept `CAP_SYS_NICE` instead. Modifying processes owned
+    /// by other users additionally requires `CAP_SYS_NICE`, which depends on the target; refer to
+    /// [`can_set`] for a check that takes both into account.
+    pub fn required_capabilities(&self) -> &'static [Capability] {
+        match self.class() {
+            Some(Class::Realtime(_)) => &[Capability::SysAdmin],
+            _ => &[],
+        }
+    }
+    /// The preset for background work, which is simply the [`Idle`](Class::Idle) class.
+    pub const fn background() -> Self {
+        Self::new(Class::Idle)
+    }
+    /// The preset for foreground work, which is the [`BestEffort`](Class::BestEffort) class at the
+    /// [fallback level](BePriorityLevel::fallback) 4.
+    pub const fn foreground() -> Self {
+        Self::new(Class::BestEffort(BePriorityLevel::fallback()))
+    }
     /// Get the inner I/O priority mask, which can be set in several interfaces, including
     /// `io_uring`, `AIO`, and the regular `ioprio_*` syscalls.
     pub const fn inner(self) -> u16 {
@@ -226,62 +978,1111 @@ impl Priority {
     }
     /// Construct an I/O priority from the inner value.
     ///
-    /// Note that it is up to the caller to ensure the validity of the mask.
+    /// Note that it is up to the caller to ensure the validity of the mask. Use the
+    /// [`TryFrom<u16>`](#impl-TryFrom%3Cu16%3E) implementation for a checked conversion.
     pub const fn from_inner(inner: u16) -> Self {
         Self { inner }
     }
+    /// Construct a valid I/O priority from any mask, normalizing it to the nearest valid priority
+    /// rather than failing, as opposed to the strict [`TryFrom<u16>`](#impl-TryFrom%3Cu16%3E)
+    /// implementation.
+    ///
+    /// Masks are normalized as follows:
+    ///
+    /// * Real-time and best-effort masks with a level of 8 or higher are clamped to level 7, the
+    ///   lowest level of the same class.
+    /// * Idle masks with nonzero data are reduced to the idle class.
+    /// * Masks of the none class, regardless of their data, and masks with an unknown class (4-7),
+    ///   become the [standard](Self::standard) priority.
+    ///
+    /// Valid masks are always returned unchanged.
+    pub const fn from_inner_lossy(mask: u16) -> Self {
+        let data = Self::raw_data(mask);
+
+        let inner = match Self::raw_class(mask) {
+            class @ 1 | class @ 2 => Self::prio_value(class, if data > 7 { 7 } else { data }),
+            3 => Self::prio_value(3, 0),
+            _ => 0,
+        };
+        Self { inner }
+    }
+    /// Check whether a mask is a valid priority, without constructing it.
+    ///
+    /// A mask is valid if its class is one of 0-3, the level of the real-time and best-effort
+    /// classes is in the range 0-7, and the data of the idle and none classes is zero. The zero
+    /// mask is thus valid, being the [standard](Self::standard) priority. This is the same check
+    /// as the one done by the [`TryFrom<u16>`](#impl-TryFrom%3Cu16%3E-for-Priority)
+    /// implementation.
+    pub const fn is_valid(mask: u16) -> bool {
+        let data = Self::raw_data(mask);
+
+        match Self::raw_class(mask) {
+            1 | 2 => data < 8,
+            0 | 3 => data == 0,
+            _ => false,
+        }
+    }
+    /// Build a raw mask from a raw class and per-class data, like the `IOPRIO_PRIO_VALUE` macro.
+    ///
+    /// A mask consists of the class in bits 13-15 (`IOPRIO_CLASS_SHIFT`), and the data in bits
+    /// 0-12, where the classes are 0 for none, 1 for real-time, 2 for best-effort, and 3 for idle,
+    /// and the data is the level for the real-time and best-effort classes. As with the macro,
+    /// neither value is validated, and data wider than 13 bits will overlap the class.
+    pub const fn prio_value(class: u16, data: u16) -> u16 {
+        (class << 13) | data
+    }
+    /// Extract the raw class from bits 13-15 of a mask, like the `IOPRIO_PRIO_CLASS` macro.
+    pub const fn raw_class(mask: u16) -> u16 {
+        mask >> 13
+    }
+    /// Extract the raw data from bits 0-12 of a mask, like the `IOPRIO_PRIO_DATA` macro.
+    pub const fn raw_data(mask: u16) -> u16 {
+        mask & 0x1FFF
+    }
+    /// Get the inner mask as bytes in big-endian (network) byte order.
+    ///
+    /// Note that this is only the kernel mask returned by [`inner`](Self::inner) with a fixed
+    /// byte order, and not any particular wire format.
+    pub const fn to_be_bytes(self) -> [u8; 2] {
+        self.inner.to_be_bytes()
+    }
+    /// Get the inner mask as bytes in little-endian byte order.
+    ///
+    /// Note that this is only the kernel mask returned by [`inner`](Self::inner) with a fixed
+    /// byte order, and not any particular wire format.
+    pub const fn to_le_bytes(self) -> [u8; 2] {
+        self.inner.to_le_bytes()
+    }
+    /// Decode a mask from bytes in big-endian (network) byte order, failing if the mask is
+    /// invalid.
+    pub fn from_be_bytes(bytes: [u8; 2]) -> Result<Self, InvalidMask> {
+        Self::try_from(u16::from_be_bytes(bytes))
+    }
+    /// Decode a mask from bytes in little-endian byte order, failing if the mask is invalid.
+    pub fn from_le_bytes(bytes: [u8; 2]) -> Result<Self, InvalidMask> {
+        Self::try_from(u16::from_le_bytes(bytes))
+    }
+    /// Write this priority into the `ioprio` field of a raw `io_uring_sqe`, given as bytes.
+    ///
+    /// This is meant for rings that are managed without any io_uring crate. The field is located
+    /// at bytes 2-3 of the SQE, following the one-byte `opcode` and `flags` fields, and is stored
+    /// in the native byte order, like every other field shared with the kernel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slice is shorter than [`SQE_SIZE`], the size of a regular SQE.
+    pub fn write_to_sqe_bytes(self, sqe: &mut [u8]) {
+        assert!(sqe.len() >= SQE_SIZE, "an SQE is at least 64 bytes long");
+        sqe[SQE_IOPRIO_OFFSET..SQE_IOPRIO_OFFSET + 2].copy_from_slice(&self.inner.to_ne_bytes());
+    }
+    /// Read the priority from the `ioprio` field of a raw `io_uring_sqe`, given as bytes.
+    ///
+    /// This is the counterpart of [`write_to_sqe_bytes`](Self::write_to_sqe_bytes), and returns the
+    /// mask as-is, without checking that it is valid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slice is shorter than [`SQE_SIZE`], the size of a regular SQE.
+    pub fn read_from_sqe_bytes(sqe: &[u8]) -> Self {
+        assert!(sqe.len() >= SQE_SIZE, "an SQE is at least 64 bytes long");
+        let bytes = [sqe[SQE_IOPRIO_OFFSET], sqe[SQE_IOPRIO_OFFSET + 1]];
+
+        Self {
+            inner: u16::from_ne_bytes(bytes),
+        }
+    }
 }
+/// The size of a regular `io_uring_sqe` in bytes, as opposed to the 128-byte SQEs of rings set up
+/// with `IORING_SETUP_SQE128`.
+pub const SQE_SIZE: usize = 64;
+/// The offset of the `ioprio` field in an `io_uring_sqe`.
+const SQE_IOPRIO_OFFSET: usize = 2;
+/// Every valid priority, in canonical order.
+///
+/// This begins with the [standard](Priority::standard) priority, followed by real-time levels 0
+/// to 7, best-effort levels 0 to 7, and finally idle, for a total of 18 priorities. Apart from the
+/// standard priority, this is ordered from the highest to the lowest priority. The same priorities
+/// are yielded by [`Priority::all`].
+pub static ALL_PRIORITIES: &[Priority] = &[
+    Priority::standard(),
+    Priority::from_class_data(1, 0),
+    Priority::from_class_data(1, 1),
+    Priority::from_class_data(1, 2),
+    Priority::from_class_data(1, 3),
+    Priority::from_class_data(1, 4),
+    Priority::from_class_data(1, 5),
+    Priority::from_class_data(1, 6),
+    Priority::from_class_data(1, 7),
+    Priority::from_class_data(2, 0),
+    Priority::from_class_data(2, 1),
+    Priority::from_class_data(2, 2),
+    Priority::from_class_data(2, 3),
+    Priority::from_class_data(2, 4),
+    Priority::from_class_data(2, 5),
+    Priority::from_class_data(2, 6),
+    Priority::from_class_data(2, 7),
+    Priority::from_class_data(3, 0),
+];
+
 impl Default for Priority {
     fn default() -> Self {
         Self::standard()
     }
 }
+/// Formats the priority the same way as `ionice` does, using the [`Display`](fmt::Display)
+/// implementation of its [`Class`].
+///
+/// The [standard](Priority::standard) priority is formatted as `none`. Since some kernels report
+/// threads without a class as having level 4, such a mask is formatted as `none: prio 4`, which is
+/// also what `ionice` prints for them, and which the [`FromStr`](core::str::FromStr)
+/// implementation parses back into the same mask. Masks that cannot be decoded are formatted as
+/// `unknown`, followed by the raw mask, such as `unknown: 0x8000`.
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let data = Self::raw_data(self.inner);
 
-fn target_which_who(target: Target) -> [libc::c_int; 2] {
-    match target {
-        Target::Process(pid) => [1, pid.as_raw() as libc::c_int],
-        Target::ProcessGroup(pgid) => [2, pgid.as_raw() as libc::c_int],
-        Target::User(uid) => [3, uid.as_raw() as libc::c_int],
+        match self.class() {
+            Some(Class::None) if data != 0 => write!(f, "none: prio {}", data),
+            Some(class) => fmt::Display::fmt(&class, f),
+            None => write!(f, "unknown: {:#06x}", self.inner),
+        }
     }
 }
+impl TryFrom<u16> for Priority {
+    type Error = InvalidMask;
 
-/// Get the I/O priority of the processes of the given target.
-///
-/// If there are multiple processes, each with different priorities, then the highest priority of
-/// them will be returned.
-///
-/// Refer to _ioprio_get(2)_ for further information.
-pub fn get_priority(target: Target) -> nix::Result<Priority> {
-    let [which, who] = target_which_who(target);
-
-    let res = unsafe { libc::syscall(libc::SYS_ioprio_get, which, who) };
-
-    Errno::result(res).map(|mask| Priority { inner: mask as u16 })
+    /// Convert an inner mask to a priority, failing unless the mask is the exact mask of a valid
+    /// [`Class`], which includes the standard priority.
+    ///
+    /// That is, the class must be one of 0-3, the level of real-time and best-effort priorities
+    /// must be in the range 0-7, and the data of the idle and none classes must be zero, with the
+    /// violated rule given by [`InvalidMask::reason`].
+    fn try_from(mask: u16) -> Result<Self, Self::Error> {
+        if Self::is_valid(mask) {
+            Ok(Self::from_inner(mask))
+        } else {
+            Err(InvalidMask { mask })
+        }
+    }
 }
-/// Set the I/O priority of the processes of the given target.
-///
-/// Note that increasing the priority class to real-time, will require elevated privileges (namely
-/// `CAP_SYS_ADMIN`). Additionally, this process must also have the permissions to modify the
-/// target process or group, or have `CAP_SYS_NICE`.
-///
-/// Refer to _ioprio_set(2)_ for further information.
-pub fn set_priority(target: Target, priority: Priority) -> nix::Result<()> {
-    let [which, who] = target_which_who(target);
-
-    let res = unsafe {
-        libc::syscall(
-            libc::SYS_ioprio_set,
-            which,
-            who,
-            priority.inner as libc::c_int,
-        )
-    };
 
-    Errno::result(res).map(|_| ())
+impl From<Class> for Priority {
+    fn from(class: Class) -> Self {
+        Self::new(class)
+    }
 }
+impl TryFrom<Priority> for Class {
+    type Error = InvalidMask;
 
-#[cfg(any(doc, feature = "iou"))]
-mod sqe_ext {
+    /// Get the class of a priority, failing under the same conditions as the
+    /// [`TryFrom<u16>`](Priority#impl-TryFrom%3Cu16%3E-for-Priority) implementation of
+    /// [`Priority`].
+    ///
+    /// Unlike [`Priority::class`], this also rejects masks with data that the class does not use,
+    /// and thus converting the class back into a priority always yields the same priority. The
+    /// [standard](Priority::standard) priority is converted into [`Class::None`].
+    fn try_from(priority: Priority) -> Result<Self, Self::Error> {
+        let priority = Priority::try_from(priority.inner)?;
+        Ok(priority.class().expect("valid masks always have a class"))
+    }
+}
+
+/// A decoded priority, as returned by [`Priority::decoded`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DecodedPriority {
+    /// A priority with a known class, other than [`Class::None`].
+    Known(Class),
+    /// The [standard](Priority::standard) priority, or any other mask of the [`None`](Class::None)
+    /// class.
+    Standard,
+    /// A mask that could not be decoded, either due to an unknown class, or a level out of range.
+    Unknown(u16),
+}
+
+/// The error returned when trying to convert a mask that is not a valid I/O priority.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct InvalidMask {
+    mask: u16,
+}
+impl InvalidMask {
+    /// Get the mask that failed to convert.
+    pub const fn mask(self) -> u16 {
+        self.mask
+    }
+    /// Get the reason why the mask is invalid.
+    pub fn reason(self) -> InvalidMaskReason {
+        let class_raw = Priority::raw_class(self.mask);
+
+        match class_raw {
+            1 | 2 => InvalidMaskReason::LevelOutOfRange,
+            0 | 3 => InvalidMaskReason::UnexpectedData,
+            _ => InvalidMaskReason::UnknownClass,
+        }
+    }
+}
+impl fmt::Display for InvalidMask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let class_raw = Priority::raw_class(self.mask);
+        let data = Priority::raw_data(self.mask);
+
+        write!(f, "invalid I/O priority mask {:#06x}: ", self.mask)?;
+
+        match self.reason() {
+            InvalidMaskReason::UnknownClass => write!(f, "unknown class {}", class_raw),
+            InvalidMaskReason::LevelOutOfRange => write!(f, "level {} is not in 0-7", data),
+            InvalidMaskReason::UnexpectedData => {
+                write!(
+                    f,
+                    "class {} does not take a level, but got {}",
+                    class_raw, data
+                )
+            }
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidMask {}
+
+/// The reason why a mask is not a valid I/O priority, as returned by [`InvalidMask::reason`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum InvalidMaskReason {
+    /// The class bits are neither of the classes 0-3.
+    UnknownClass,
+    /// The level of a real-time or best-effort priority is 8 or greater.
+    LevelOutOfRange,
+    /// The data of the idle class or the [`None`](Class::None) class is nonzero.
+    UnexpectedData,
+}
+
+/// The error returned when getting or setting priorities, with the common causes given their own
+/// variants.
+///
+/// Every error originates from an error number, which is classified when converting it using
+/// [`From`], and which is always available through [`raw`](Self::raw). Conversions to and from
+/// [`nix::Error`] are provided for interoperability with code using `nix` directly.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// The caller is not permitted to set the priority (`EPERM`). Setting the real-time class
+    /// requires `CAP_SYS_ADMIN`, and modifying processes owned by other users requires
+    /// `CAP_SYS_NICE`.
+    PermissionDenied,
+    /// No process matched the target (`ESRCH`).
+    InvalidTarget,
+    /// Either the priority mask or the target kind was rejected by the kernel (`EINVAL`).
+    InvalidPriority,
+    /// Any other error.
+    Other(Errno),
+}
+#[cfg(feature = "std")]
+impl Error {
+    /// Get the underlying error number.
+    pub fn raw(self) -> Errno {
+        match self {
+            Self::PermissionDenied => Errno::EPERM,
+            Self::InvalidTarget => Errno::ESRCH,
+            Self::InvalidPriority => Errno::EINVAL,
+            Self::Other(errno) => errno,
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl From<Errno> for Error {
+    fn from(errno: Errno) -> Self {
+        match errno {
+            Errno::EPERM => Self::PermissionDenied,
+            Errno::ESRCH => Self::InvalidTarget,
+            Errno::EINVAL => Self::InvalidPriority,
+            other => Self::Other(other),
+        }
+    }
+}
+/// Errors that do not carry any error number, which this crate never returns, are converted to
+/// [`Other`](Error::Other) with [`Errno::UnknownErrno`].
+#[cfg(feature = "std")]
+impl From<nix::Error> for Error {
+    fn from(error: nix::Error) -> Self {
+        error.as_errno().unwrap_or(Errno::UnknownErrno).into()
+    }
+}
+#[cfg(feature = "std")]
+impl From<Error> for nix::Error {
+    fn from(error: Error) -> Self {
+        nix::Error::Sys(error.raw())
+    }
+}
+#[cfg(feature = "std")]
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PermissionDenied => f.write_str(
+                "permission denied: the real-time class requires CAP_SYS_ADMIN, and processes of \
+                 other users require CAP_SYS_NICE",
+            ),
+            Self::InvalidTarget => f.write_str("no process matches the target"),
+            Self::InvalidPriority => f.write_str("invalid I/O priority or target"),
+            Self::Other(errno) => f.write_str(errno.desc()),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// The result type returned when getting or setting priorities, with [`Error`] as the default
+/// error type.
+#[cfg(feature = "std")]
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+/// Whether two targets may match the same process, as determined by [`Target::overlaps`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum OverlapResult {
+    /// The targets are guaranteed to match the same processes, since they are identical.
+    Definite,
+    /// The targets may or may not have processes in common, which can only be determined by
+    /// inspecting the processes themselves.
+    Possible,
+    /// The targets can never match the same process.
+    None,
+}
+
+#[cfg(feature = "std")]
+impl Target {
+    /// Determine whether this target and another target could match the same process.
+    ///
+    /// This is a pure function that never inspects any processes, and thus only resolves the cheap
+    /// cases: identical targets are a [`Definite`](OverlapResult::Definite) overlap, while two
+    /// distinct targets of the same kind can never overlap, as every process belongs to exactly
+    /// one process group and one user. A target with a PID of zero refers to the caller, and can
+    /// thus only be compared to another zero-PID target of the same kind.
+    ///
+    /// All remaining cases, such as a process and a process group, or a process group and a user,
+    /// are reported as [`Possible`](OverlapResult::Possible). A definitive answer for those would
+    /// require enumerating the processes in `/proc`, and would still be racy.
+    pub fn overlaps(&self, other: &Target) -> OverlapResult {
+        match (self.thread_as_process(), other.thread_as_process()) {
+            (lhs, rhs) if lhs == rhs => OverlapResult::Definite,
+
+            (Self::Process(lhs), Self::Process(rhs))
+            | (Self::ProcessGroup(lhs), Self::ProcessGroup(rhs)) => {
+                if lhs.as_raw() == 0 || rhs.as_raw() == 0 {
+                    OverlapResult::Possible
+                } else {
+                    OverlapResult::None
+                }
+            }
+            (Self::User(_), Self::User(_)) => OverlapResult::None,
+
+            _ => OverlapResult::Possible,
+        }
+    }
+    /// Get a target referring to the caller, as a process with a PID of zero.
+    ///
+    /// Since the kernel resolves a PID of zero to the calling thread rather than the entire
+    /// process, this only affects the calling thread, like [`current_thread`](Self::current_thread)
+    /// does, but unlike it, refers to whichever thread it is used from.
+    pub fn current_process() -> Self {
+        Self::Process(Pid::from_raw(0))
+    }
+    /// Get a target referring to the process group of the caller, with a PGID of zero.
+    pub fn current_process_group() -> Self {
+        Self::ProcessGroup(Pid::from_raw(0))
+    }
+    /// Get a target referring to the calling thread, by its TID rather than by zero.
+    ///
+    /// Unlike a TID of zero, the returned target keeps referring to the same thread when passed to
+    /// other threads.
+    pub fn current_thread() -> Self {
+        Self::Thread(gettid())
+    }
+    /// Get the `which` argument of the `ioprio_*` syscalls for this target, that is 1 for
+    /// processes and threads (`IOPRIO_WHO_PROCESS`), 2 for process groups (`IOPRIO_WHO_PGRP`),
+    /// and 3 for users (`IOPRIO_WHO_USER`).
+    pub fn raw_which(&self) -> i32 {
+        target_which_who(*self)[0]
+    }
+    /// Get the `who` argument of the `ioprio_*` syscalls for this target, that is the raw PID,
+    /// TID, PGID, or UID.
+    pub fn raw_who(&self) -> i32 {
+        target_which_who(*self)[1]
+    }
+    /// Get the PID, TID, or PGID of this target, or [`None`] for a user.
+    pub fn as_pid(&self) -> Option<Pid> {
+        match *self {
+            Self::Process(pid) | Self::ProcessGroup(pid) | Self::Thread(pid) => Some(pid),
+            Self::User(_) => None,
+        }
+    }
+    /// Get the UID of this target if it is a user, or [`None`] otherwise.
+    pub fn as_uid(&self) -> Option<Uid> {
+        match *self {
+            Self::User(uid) => Some(uid),
+            _ => None,
+        }
+    }
+    /// Convert a [`Thread`](Self::Thread) target to the equivalent [`Process`](Self::Process)
+    /// target, which the kernel treats identically.
+    fn thread_as_process(self) -> Self {
+        match self {
+            Self::Thread(tid) => Self::Process(tid),
+            other => other,
+        }
+    }
+    /// Express this target as arguments to the `ionice` command, that is `["-p", pid]`, `["-P",
+    /// pgid]`, or `["-u", uid]`, where a thread is passed using `-p` as well.
+    ///
+    /// Note that a zero PID is passed through as-is, and will thus refer to the `ionice` process
+    /// (or its process group) rather than the caller.
+    pub fn to_ionice_args(&self) -> Vec<String> {
+        let (flag, id) = match *self {
+            Self::Process(pid) | Self::Thread(pid) => ("-p", pid.to_string()),
+            Self::ProcessGroup(pgid) => ("-P", pgid.to_string()),
+            Self::User(uid) => ("-u", uid.to_string()),
+        };
+        vec![flag.to_owned(), id]
+    }
+}
+
+/// A conflict between two priority plans, as returned by [`plans_conflict`].
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Conflict {
+    left: (Target, Priority),
+    right: (Target, Priority),
+    overlap: OverlapResult,
+}
+#[cfg(feature = "std")]
+impl Conflict {
+    /// Get the target and priority from the first plan.
+    pub fn left(&self) -> (Target, Priority) {
+        self.left
+    }
+    /// Get the target and priority from the second plan.
+    pub fn right(&self) -> (Target, Priority) {
+        self.right
+    }
+    /// Get whether the targets are known to overlap, or only possibly overlap.
+    pub fn overlap(&self) -> OverlapResult {
+        self.overlap
+    }
+}
+
+/// Find every pair of entries from two plans, that assign different priorities to overlapping
+/// targets.
+///
+/// Each entry of the first plan is compared with each entry of the second plan, and is reported
+/// as a conflict if the targets [overlap](Target::overlaps) either definitely or possibly, and the
+/// priorities differ in their effective urgency. Thus the [standard](Priority::standard) priority
+/// and best-effort level 4 do not conflict, whereas masks that cannot be decoded conflict with
+/// every other mask than themselves. The conflicts are returned in the order of the first plan,
+/// and then the second.
+#[cfg(feature = "std")]
+pub fn plans_conflict(a: &[(Target, Priority)], b: &[(Target, Priority)]) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+
+    for &left in a {
+        for &right in b {
+            let overlap = left.0.overlaps(&right.0);
+
+            if overlap == OverlapResult::None {
+                continue;
+            }
+            let (lhs, rhs) = (left.1.effective_rank(), right.1.effective_rank());
+
+            if left.1 == right.1 || (lhs.is_some() && lhs == rhs) {
+                continue;
+            }
+            conflicts.push(Conflict {
+                left,
+                right,
+                overlap,
+            });
+        }
+    }
+    conflicts
+}
+
+#[cfg(feature = "std")]
+fn target_which_who(target: Target) -> [libc::c_int; 2] {
+    match target {
+        Target::Process(pid) | Target::Thread(pid) => [1, pid.as_raw() as libc::c_int],
+        Target::ProcessGroup(pgid) => [2, pgid.as_raw() as libc::c_int],
+        Target::User(uid) => [3, uid.as_raw() as libc::c_int],
+    }
+}
+
+/// Get the I/O priority of the processes of the given target.
+///
+/// If there are multiple processes, each with different priorities, then the highest priority of
+/// them will be returned.
+///
+/// The kernel stores priorities as 16-bit masks, and thus never returns anything but a negative
+/// error code or a value that fits in a `u16`. Should that ever not be the case, `EOVERFLOW` is
+/// returned rather than silently truncating the value. Note that the mask is returned as-is, and
+/// is not required to decode into a [`Class`].
+///
+/// Refer to _ioprio_get(2)_ for further information.
+#[cfg(feature = "std")]
+pub fn get_priority(target: Target) -> Result<Priority> {
+    let mask = get_priority_raw(target)?;
+
+    u16::try_from(mask)
+        .map(|inner| Priority { inner })
+        .map_err(|_| Error::Other(Errno::EOVERFLOW))
+}
+/// Get the I/O priority of the processes of the given target, as the unmodified return value of
+/// the syscall.
+///
+/// Unlike [`get_priority`], this does not check that the value fits in a 16-bit mask, but errors
+/// are still reported as such rather than as negative values.
+#[cfg(feature = "std")]
+pub fn get_priority_raw(target: Target) -> Result<i64> {
+    let [which, who] = target_which_who(target);
+
+    // Neither `nix` nor `rustix` provide wrappers for `ioprio_get` and `ioprio_set`, and `rustix`
+    // does not expose raw syscalls either, so both are issued through `libc` directly.
+    let res = unsafe { libc::syscall(libc::SYS_ioprio_get, which, who) };
+
+    // `c_long` is only 32 bits wide on some targets.
+    #[allow(clippy::useless_conversion)]
+    Errno::result(res).map(i64::from).map_err(Error::from)
+}
+/// Set the I/O priority of the processes of the given target.
+///
+/// Note that increasing the priority class to real-time, will require elevated privileges (namely
+/// `CAP_SYS_ADMIN`). Additionally, this process must also have the permissions to modify the
+/// target process or group, or have `CAP_SYS_NICE`.
+///
+/// Refer to _ioprio_set(2)_ for further information.
+#[cfg(feature = "std")]
+pub fn set_priority(target: Target, priority: Priority) -> Result<()> {
+    let [which, who] = target_which_who(target);
+
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            which,
+            who,
+            priority.inner as libc::c_int,
+        )
+    };
+
+    Errno::result(res).map(|_| ()).map_err(Error::from)
+}
+/// Get the I/O priority of the calling thread.
+///
+/// This is a shorthand for [`get_priority`] with [`Target::current_process`].
+#[cfg(feature = "std")]
+pub fn get_current_priority() -> Result<Priority> {
+    get_priority(Target::current_process())
+}
+/// Set the I/O priority of the calling thread.
+///
+/// This is a shorthand for [`set_priority`] with [`Target::current_process`]. Note that other
+/// threads of the process are left unchanged, although threads spawned afterwards inherit the
+/// priority.
+#[cfg(feature = "std")]
+pub fn set_current_priority(priority: Priority) -> Result<()> {
+    set_priority(Target::current_process(), priority)
+}
+/// Reset the I/O priority of the given target to the [standard](Priority::standard) priority, such
+/// that it is again derived from the CPU nice value and scheduling policy.
+#[cfg(feature = "std")]
+pub fn reset_priority(target: Target) -> Result<()> {
+    set_priority(target, Priority::standard())
+}
+/// Set the I/O priority of every thread in the calling process.
+///
+/// Unlike [`set_current_priority`], which only affects the calling thread, this lists every thread
+/// in `/proc/self/task`, and sets the priority of each of them individually. Threads that exit
+/// before their priority is set are skipped. If setting the priority of any other thread fails,
+/// the remaining threads are still set, after which the first error is returned.
+///
+/// Threads spawned while this is running may or may not be included, although they will inherit
+/// the new priority if spawned from a thread that has already been set.
+#[cfg(feature = "std")]
+pub fn set_all_threads_priority(priority: Priority) -> Result<()> {
+    let mut first_error = None;
+
+    for tid in procfs::threads(getpid())? {
+        match set_priority(Target::Thread(tid), priority) {
+            Ok(()) | Err(Error::InvalidTarget) => continue,
+            Err(error) => {
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+/// Set the I/O priority of the given target, but only if a predicate on the class of its current
+/// priority holds, returning whether the priority was set.
+///
+/// The predicate receives the result of [`Priority::class`], which is [`Class::None`] for the
+/// standard priority, and [`None`] for masks that cannot be decoded. This allows rules such as
+/// only changing targets that are currently idle, without issuing a redundant syscall to set the
+/// priority of every other target.
+///
+/// Note that the priority is read and set in two separate syscalls, so the target may be modified
+/// by someone else in between.
+#[cfg(feature = "std")]
+pub fn set_priority_if<F>(target: Target, priority: Priority, cond: F) -> Result<bool>
+where
+    F: FnOnce(Option<Class>) -> bool,
+{
+    if !cond(get_priority(target)?.class()) {
+        return Ok(false);
+    }
+    set_priority(target, priority).map(|_| true)
+}
+
+/// Set the same I/O priority for multiple targets, continuing past any failures.
+///
+/// Unlike calling [`set_priority`] in a loop with `?`, a single target failing, such as a process
+/// that has already exited and thus yields [`Error::InvalidTarget`], does not prevent the
+/// remaining targets from being set. The result of each target is returned in the same order as
+/// the targets.
+#[cfg(feature = "std")]
+pub fn set_priority_bulk(targets: &[Target], priority: Priority) -> Vec<(Target, Result<()>)> {
+    targets
+        .iter()
+        .map(|&target| (target, set_priority(target, priority)))
+        .collect()
+}
+/// Get the I/O priorities of multiple processes, continuing past any failures.
+///
+/// This is the reading counterpart of [`set_priority_bulk`], with one syscall per PID. A process
+/// that has exited, and thus yields [`Error::InvalidTarget`], has its error recorded rather than
+/// aborting the remaining reads. The result of each PID is returned in the same order as the PIDs.
+#[cfg(feature = "std")]
+pub fn get_priorities(pids: &[Pid]) -> Vec<(Pid, Result<Priority>)> {
+    pids.iter()
+        .map(|&pid| (pid, get_priority(Target::Process(pid))))
+        .collect()
+}
+
+/// Determine whether setting the I/O priority of the given target would modify any process owned
+/// by a different user than the caller.
+///
+/// Without `CAP_SYS_NICE`, the kernel only allows modifying a process whose real UID matches the
+/// real or effective UID of the calling process. This can thus be used to flag operations that
+/// either require elevated privileges, or that could unexpectedly affect other users.
+///
+/// For [`Target::User`], this is a simple comparison of UIDs, and for [`Target::Process`] and
+/// [`Target::Thread`], only the owner of that single thread is read from `/proc`.
+/// [`Target::ProcessGroup`] however requires enumerating every process on the system in order to
+/// find the members of the group, which is linear in the total number of processes.
+///
+/// Note that the result is only advisory: processes may be created, exit, or change credentials
+/// or process groups, between calling this function and actually setting the priority.
+#[cfg(feature = "std")]
+pub fn set_would_cross_user_boundary(target: Target) -> Result<bool> {
+    let is_foreign = |uid: Uid| uid != getuid() && uid != geteuid();
+
+    match target {
+        Target::User(uid) => Ok(is_foreign(uid)),
+        Target::Process(pid) | Target::Thread(pid) if pid.as_raw() == 0 => Ok(false),
+        Target::Process(pid) | Target::Thread(pid) => procfs::real_uid(pid).map(is_foreign),
+        Target::ProcessGroup(pgid) => {
+            let pgid = if pgid.as_raw() == 0 { getpgrp() } else { pgid };
+
+            for pid in procfs::group_members(pgid)? {
+                match procfs::real_uid(pid) {
+                    Ok(uid) if is_foreign(uid) => return Ok(true),
+                    Ok(_) | Err(Error::InvalidTarget) => continue,
+                    Err(error) => return Err(error),
+                }
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// A Linux capability that is relevant to setting I/O priorities, as returned by
+/// [`Priority::required_capabilities`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Capability {
+    /// `CAP_SYS_ADMIN`, required for setting the real-time class.
+    SysAdmin,
+    /// `CAP_SYS_NICE`, required for modifying processes owned by other users, and which also
+    /// allows setting the real-time class since Linux 5.11.
+    SysNice,
+}
+#[cfg(feature = "std")]
+impl Capability {
+    fn number(self) -> u32 {
+        match self {
+            Self::SysAdmin => 21,
+            Self::SysNice => 23,
+        }
+    }
+}
+
+/// Determine whether the calling thread is likely permitted to set the I/O priority of a target,
+/// without actually setting it.
+///
+/// This checks the effective capabilities of the calling thread, read from `/proc`, against both
+/// the [capabilities required](Priority::required_capabilities) by the priority, and
+/// `CAP_SYS_NICE` if the target [crosses a user boundary](set_would_cross_user_boundary). Since
+/// Linux 5.11 also permits `CAP_SYS_NICE` for the real-time class, either capability is accepted
+/// for it, which may be overly optimistic on older kernels.
+///
+/// The result is only advisory, and racy in the same way as `set_would_cross_user_boundary`. Other
+/// security modules may also deny the operation, so the error returned by [`set_priority`]
+/// remains the authoritative answer.
+#[cfg(feature = "std")]
+pub fn can_set(target: Target, priority: Priority) -> Result<bool> {
+    let capabilities = procfs::effective_capabilities()?;
+
+    // Crossing a user boundary does not matter with `CAP_SYS_NICE`, and determining it requires
+    // enumerating every process for process groups.
+    let crosses_user = capabilities & (1 << Capability::SysNice.number()) == 0
+        && set_would_cross_user_boundary(target)?;
+
+    Ok(can_set_with(capabilities, crosses_user, priority))
+}
+/// Determine the result of [`can_set`], given the effective capabilities of the calling thread as
+/// a bit mask, and whether setting the priority would cross a user boundary.
+#[cfg(feature = "std")]
+fn can_set_with(capabilities: u64, crosses_user: bool, priority: Priority) -> bool {
+    let has = |capability: Capability| capabilities & (1 << capability.number()) != 0;
+
+    if !priority.required_capabilities().is_empty()
+        && !has(Capability::SysAdmin)
+        && !has(Capability::SysNice)
+    {
+        return false;
+    }
+    has(Capability::SysNice) || !crosses_user
+}
+/// Set the I/O priority of a target like [`set_priority`], but fail early with
+/// [`Error::PermissionDenied`] if [`can_set`] determines that the calling thread lacks the required
+/// permissions, most notably when requesting the real-time class without `CAP_SYS_ADMIN` (or
+/// `CAP_SYS_NICE`).
+///
+/// The error returned early is the same as the one the syscall itself would return. If the
+/// precheck cannot be performed at all, for example because `/proc` is not mounted, the syscall
+/// is issued regardless. Since the permissions of the
+/// calling thread may change between the check and the syscall, this is only an optimization, and
+/// never a guarantee in either direction.
+#[cfg(feature = "std")]
+pub fn set_priority_checked(target: Target, priority: Priority) -> Result<()> {
+    if let Ok(false) = can_set(target, priority) {
+        return Err(Error::PermissionDenied);
+    }
+    set_priority(target, priority)
+}
+
+/// Determine whether all threads matching the target share the same I/O priority.
+///
+/// For process groups and users, [`get_priority`] only returns the highest priority among the
+/// matching threads, which may or may not be representative of all of them. This instead reads the
+/// priority of every matching thread individually, and compares them. A single process is
+/// trivially homogeneous, as it only ever refers to a single thread.
+///
+/// Finding the members of a process group or user requires enumerating every process on the
+/// system through `/proc`, followed by one syscall per matching thread. Threads that exit during
+/// the enumeration are skipped, and [`Error::InvalidTarget`] is returned if no threads match at
+/// all.
+#[cfg(feature = "std")]
+pub fn is_homogeneous(target: Target) -> Result<bool> {
+    if let Target::Process(_) | Target::Thread(_) = target {
+        return Ok(true);
+    }
+    let mut first = None;
+
+    for tid in procfs::target_threads(target)? {
+        let priority = match get_restorable_priority(Target::Process(tid)) {
+            Ok(priority) => priority,
+            Err(Error::InvalidTarget) => continue,
+            Err(error) => return Err(error),
+        };
+        match first {
+            None => first = Some(priority),
+            Some(first) if first != priority => return Ok(false),
+            Some(_) => (),
+        }
+    }
+    first.map(|_| true).ok_or(Error::InvalidTarget)
+}
+
+/// Get the priority of a target, in a form that can later be passed back to [`set_priority`].
+#[cfg(feature = "std")]
+fn get_restorable_priority(target: Target) -> Result<Priority> {
+    let priority = get_priority(target)?;
+
+    // Some kernels report a thread that has never been assigned a priority, as having no class but
+    // a nonzero level, which the kernel itself will refuse to set.
+    if Priority::raw_class(priority.inner) == 0 {
+        Ok(Priority::standard())
+    } else {
+        Ok(priority)
+    }
+}
+
+/// A guard that sets the I/O priority of the calling thread, and restores the previous priority
+/// when dropped.
+///
+/// I/O priorities belong to individual threads rather than entire processes, which is why this
+/// guard can neither be sent to nor shared with other threads. Any error when restoring the
+/// previous priority is ignored.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+#[must_use = "the previous priority is restored as soon as the guard is dropped"]
+pub struct ThreadPriorityGuard {
+    previous: Priority,
+    _not_send: PhantomData<*const ()>,
+}
+#[cfg(feature = "std")]
+impl ThreadPriorityGuard {
+    /// Set the I/O priority of the calling thread, remembering the current priority.
+    pub fn new(priority: Priority) -> Result<Self> {
+        let target = Target::current_process();
+
+        let previous = get_restorable_priority(target)?;
+        set_priority(target, priority)?;
+
+        Ok(Self {
+            previous,
+            _not_send: PhantomData,
+        })
+    }
+    /// Get the priority that will be restored when this guard is dropped.
+    pub fn previous(&self) -> Priority {
+        self.previous
+    }
+}
+#[cfg(feature = "std")]
+impl Drop for ThreadPriorityGuard {
+    fn drop(&mut self) {
+        let _ = set_priority(Target::current_process(), self.previous);
+    }
+}
+
+/// Switch the calling thread to the [background](Priority::background) preset, the idle class,
+/// until the returned guard is dropped.
+#[cfg(feature = "std")]
+pub fn enter_background_mode() -> Result<ThreadPriorityGuard> {
+    ThreadPriorityGuard::new(Priority::background())
+}
+/// Switch the calling thread to the [foreground](Priority::foreground) preset, best-effort level 4,
+/// until the returned guard is dropped.
+#[cfg(feature = "std")]
+pub fn enter_foreground_mode() -> Result<ThreadPriorityGuard> {
+    ThreadPriorityGuard::new(Priority::foreground())
+}
+
+/// Check that the running kernel interprets priority masks the same way as this crate, by setting
+/// every [valid priority](Priority::all) on the calling thread and immediately reading it back.
+///
+/// Every priority that reads back differently is returned along with the priority that was read,
+/// such that an empty list means the encoding matches. Priorities requiring capabilities the
+/// calling thread lacks, such as the real-time class without `CAP_SYS_ADMIN`, are skipped when
+/// setting them fails with [`Error::PermissionDenied`]. The original priority of the calling
+/// thread is restored afterwards, even if an error is returned. Since some kernels report the
+/// [standard](Priority::standard) priority with a nonzero level, any priority without a class is
+/// read back as the standard priority.
+///
+/// This is primarily intended as a diagnostic for unusual kernels, and is not needed in general.
+#[cfg(feature = "std")]
+pub fn verify_encoding() -> Result<Vec<(Priority, Priority)>> {
+    let target = Target::current_process();
+    let _guard = ThreadPriorityGuard::new(Priority::standard())?;
+
+    let mut mismatches = Vec::new();
+
+    for priority in Priority::all() {
+        match set_priority(target, priority) {
+            Ok(()) => (),
+            Err(Error::PermissionDenied) if !priority.required_capabilities().is_empty() => {
+                continue
+            }
+            Err(error) => return Err(error),
+        }
+        let actual = get_restorable_priority(target)?;
+
+        if actual != priority {
+            mismatches.push((priority, actual));
+        }
+    }
+    Ok(mismatches)
+}
+
+/// Restores the priorities of a list of targets, in reverse order, when dropped.
+#[cfg(feature = "std")]
+struct RestoreGuard {
+    previous: Vec<(Target, Priority)>,
+}
+#[cfg(feature = "std")]
+impl Drop for RestoreGuard {
+    fn drop(&mut self) {
+        for &(target, priority) in self.previous.iter().rev() {
+            let _ = set_priority(target, priority);
+        }
+    }
+}
+
+/// Set the I/O priority of a target while running a closure, restoring the previous priority
+/// afterwards, even if the closure panics.
+///
+/// If setting the priority fails, the error is returned and the closure is never run. As with
+/// [`with_lowered_priority`], restoring is only done on a best-effort basis with errors being
+/// ignored, and for process groups and users, the highest priority among their processes is what
+/// gets restored to all of them.
+#[cfg(feature = "std")]
+pub fn with_priority<F, T>(target: Target, priority: Priority, f: F) -> Result<T>
+where
+    F: FnOnce() -> T,
+{
+    let previous = get_restorable_priority(target)?;
+    set_priority(target, priority)?;
+
+    let _guard = RestoreGuard {
+        previous: vec![(target, previous)],
+    };
+    Ok(f())
+}
+
+/// Lower the I/O priority of multiple targets while running a closure, restoring the original
+/// priorities afterwards, even if the closure panics.
+///
+/// Targets are only ever lowered, never raised: a target whose current priority is already at or
+/// below the given priority, is left untouched. The priorities are compared by their effective
+/// urgency, where the [standard](Priority::standard) priority counts as best-effort level 4, which
+/// is what it resolves to at the default nice value. Targets whose current mask cannot be decoded
+/// are left untouched as well. If lowering any target fails, the targets already lowered are
+/// restored before the error is returned, and the closure is never run.
+///
+/// Restoring is only done on a best-effort basis, with errors being ignored. Since the priority of
+/// a process group or user is read as the highest priority among its processes, that single
+/// priority is what gets restored to every process matching the target, and processes may also
+/// have been created, have exited, or have been modified by someone else in the meantime.
+#[cfg(feature = "std")]
+pub fn with_lowered_priority<F, T>(targets: &[Target], priority: Priority, f: F) -> Result<T>
+where
+    F: FnOnce() -> T,
+{
+    let mut guard = RestoreGuard {
+        previous: Vec::with_capacity(targets.len()),
+    };
+
+    for &target in targets {
+        let current = get_restorable_priority(target)?;
+
+        match (priority.effective_rank(), current.effective_rank()) {
+            (Some(new), Some(old)) if new < old => (),
+            _ => continue,
+        }
+        set_priority(target, priority)?;
+        guard.previous.push((target, current));
+    }
+
+    Ok(f())
+}
+
+/// Spawn a thread that inherits an I/O priority from the calling thread, by temporarily setting
+/// the priority of the calling thread while spawning it.
+///
+/// When the kernel creates a thread (or process), the new task receives a copy of the I/O priority
+/// of the task that created it, provided that priority has been explicitly set rather than being
+/// the [standard](Priority::standard) priority. The copy is taken at clone time, so changing the
+/// priority of either thread afterwards does not affect the other (`CLONE_IO`, which would instead
+/// share the I/O context, is never used by the standard library).
+///
+/// This function relies on that inheritance: the priority of the calling thread is set using a
+/// [`ThreadPriorityGuard`], the thread is spawned, and the previous priority of the calling thread
+/// is restored as soon as the thread has been created. The new thread thus runs at the priority
+/// from its very first instruction, and any error is returned immediately, without the thread
+/// being spawned. This is in contrast to [`spawn_with_priority`], which instead sets the priority
+/// from within the new thread, and leaves the calling thread untouched. If the calling thread
+/// already has the desired priority, spawning it using [`thread::spawn`] directly is cheapest,
+/// since no syscalls are needed at all.
+#[cfg(feature = "std")]
+pub fn spawn_inheriting_current<F, T>(priority: Priority, f: F) -> Result<JoinHandle<T>>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let _guard = ThreadPriorityGuard::new(priority)?;
+
+    Ok(thread::spawn(f))
+}
+/// Spawn a thread that sets its own I/O priority, before running the closure.
+///
+/// Unlike [`spawn_inheriting_current`], this does not change the priority of the calling thread,
+/// at the cost of an additional syscall in the new thread. If setting the priority fails, the
+/// closure is never run, and the error is instead returned when joining the thread.
+#[cfg(feature = "std")]
+pub fn spawn_with_priority<F, T>(priority: Priority, f: F) -> JoinHandle<Result<T>>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    thread::spawn(move || {
+        set_current_priority(priority)?;
+        Ok(f())
+    })
+}
+/// A wrapper around [`thread::Builder`], for spawning threads that set their own I/O priority
+/// before running their closure.
+///
+/// This is the configurable counterpart of [`spawn_with_priority`], in the same way as
+/// [`thread::Builder`] is to [`thread::spawn`], and the per-thread analogue of
+/// [`CommandExt::io_priority`] for child processes.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+#[must_use = "no thread is spawned until `spawn` is called"]
+pub struct PrioritizedThread {
+    priority: Priority,
+    builder: thread::Builder,
+}
+#[cfg(feature = "std")]
+impl PrioritizedThread {
+    /// Create a builder for threads with the given priority, and otherwise the default
+    /// configuration of [`thread::Builder::new`].
+    pub fn new(priority: Priority) -> Self {
+        Self {
+            priority,
+            builder: thread::Builder::new(),
+        }
+    }
+    /// Name the thread, like [`thread::Builder::name`].
+    pub fn name(self, name: String) -> Self {
+        Self {
+            builder: self.builder.name(name),
+            ..self
+        }
+    }
+    /// Set the stack size of the thread in bytes, like [`thread::Builder::stack_size`].
+    pub fn stack_size(self, size: usize) -> Self {
+        Self {
+            builder: self.builder.stack_size(size),
+            ..self
+        }
+    }
+    /// Get the priority that the thread will set.
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+    /// Spawn the thread, which sets its I/O priority before running the closure.
+    ///
+    /// An error is returned if the thread cannot be created, like [`thread::Builder::spawn`]. If
+    /// the thread instead fails to set its priority, the closure is never run, and the error is
+    /// returned when joining the thread, as with [`spawn_with_priority`].
+    pub fn spawn<F, T>(self, f: F) -> io::Result<JoinHandle<Result<T>>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let priority = self.priority;
+
+        self.builder.spawn(move || {
+            set_current_priority(priority)?;
+            Ok(f())
+        })
+    }
+}
+
+#[cfg(feature = "iou")]
+mod sqe_ext {
     use super::*;
 
     mod private {
@@ -309,6 +2110,930 @@ mod sqe_ext {
             }
         }
     }
+
+    /// Set the priority of every SQE in a chain, such as one linked with `IOSQE_IO_LINK`.
+    ///
+    /// Every SQE carries its own priority, independently of the other SQEs it is linked with, so
+    /// this is merely a shorthand for calling [`SqeExt::set_priority`] on each of them.
+    ///
+    /// # Example
+    ///
+    /// Reading from one file and then writing the data to another, both at the idle priority:
+    ///
+    /// ```no_run
+    /// # extern crate iou_ as iou;
+    /// # fn main() -> std::io::Result<()> {
+    /// use std::fs::File;
+    /// use std::os::unix::io::AsRawFd;
+    ///
+    /// use iou::sqe::SubmissionFlags;
+    /// use ioprio::{set_chain_priority, Priority};
+    ///
+    /// let input = File::open("input")?;
+    /// let output = File::create("output")?;
+    /// let mut buf = vec![0; 4096];
+    ///
+    /// let mut ring = iou::IoUring::new(8)?;
+    /// let mut sqes: Vec<_> = ring.prepare_sqes(2).expect("ring is full").collect();
+    ///
+    /// unsafe {
+    ///     sqes[0].prep_read(input.as_raw_fd(), &mut buf[..], 0);
+    ///     sqes[0].set_flags(SubmissionFlags::IO_LINK);
+    ///     sqes[1].prep_write(output.as_raw_fd(), &buf[..], 0);
+    /// }
+    /// set_chain_priority(&mut sqes, Priority::background());
+    /// drop(sqes);
+    ///
+    /// ring.submit_sqes_and_wait(2)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_chain_priority(sqes: &mut [iou_::SQE<'_>], priority: Priority) {
+        for sqe in sqes {
+            sqe.set_priority(priority);
+        }
+    }
+}
+#[cfg(feature = "iou")]
+pub use sqe_ext::{set_chain_priority, SqeExt};
+
+#[cfg(feature = "io-uring")]
+mod entry_ext {
+    use super::*;
+
+    use io_uring_::squeue::{Entry, Entry128};
+
+    // Both entry types start with the raw `io_uring_sqe`, which is what makes the casts below
+    // sound.
+    const _: () = assert!(core::mem::size_of::<Entry>() == SQE_SIZE);
+    const _: () = assert!(core::mem::size_of::<Entry128>() == 2 * SQE_SIZE);
+
+    mod private {
+        pub trait Sealed {}
+    }
+    impl private::Sealed for Entry {}
+    impl private::Sealed for Entry128 {}
+
+    /// An extension trait for the submission queue entries of the `io-uring` crate, that allows
+    /// retrieving and setting the I/O priority of each individual I/O event, like `SqeExt` does for
+    /// the `iou` crate.
+    ///
+    /// The priority is only honored by the read and write operations, that is `Read`, `Write`,
+    /// `Readv`, `Writev`, `ReadFixed` and `WriteFixed`, which can also be given a priority when
+    /// built, using their `ioprio` method. Other operations, such as `Accept`, `Send` and `Recv`,
+    /// reuse the same field for flags of their own, so setting a priority on them changes the
+    /// operation instead.
+    pub trait EntryExt: private::Sealed {
+        /// Get the current priority stored in the entry.
+        fn priority(&self) -> Priority;
+        /// Set the priority of the entry, pertaining only to this particular I/O event.
+        fn set_priority(&mut self, priority: Priority);
+    }
+    impl EntryExt for Entry {
+        fn priority(&self) -> Priority {
+            let sqe = unsafe { &*(self as *const Entry as *const [u8; SQE_SIZE]) };
+            Priority::read_from_sqe_bytes(sqe)
+        }
+        fn set_priority(&mut self, priority: Priority) {
+            let sqe = unsafe { &mut *(self as *mut Entry as *mut [u8; SQE_SIZE]) };
+            priority.write_to_sqe_bytes(sqe);
+        }
+    }
+    impl EntryExt for Entry128 {
+        fn priority(&self) -> Priority {
+            let sqe = unsafe { &*(self as *const Entry128 as *const [u8; 2 * SQE_SIZE]) };
+            Priority::read_from_sqe_bytes(sqe)
+        }
+        fn set_priority(&mut self, priority: Priority) {
+            let sqe = unsafe { &mut *(self as *mut Entry128 as *mut [u8; 2 * SQE_SIZE]) };
+            priority.write_to_sqe_bytes(sqe);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use io_uring_::{opcode, types};
+
+        #[test]
+        fn read_entries_round_trip_their_priority() {
+            let mut buf = [0; 16];
+            let (ptr, len) = (buf.as_mut_ptr(), buf.len() as u32);
+            let read = || opcode::Read::new(types::Fd(0), ptr, len);
+
+            let mut entry = read().build().user_data(42);
+            assert_eq!(entry.priority(), Priority::standard());
+
+            entry.set_priority(Priority::idle());
+            assert_eq!(entry.priority(), Priority::idle());
+            assert_eq!(entry.get_user_data(), 42);
+
+            let built = read().ioprio(Priority::idle().inner()).build();
+            assert_eq!(built.priority(), Priority::idle());
+
+            let mut entry = Entry128::from(read().build());
+            entry.set_priority(Priority::realtime(1).unwrap());
+            assert_eq!(entry.priority(), Priority::realtime(1).unwrap());
+        }
+    }
+}
+#[cfg(feature = "io-uring")]
+pub use entry_ext::EntryExt;
+
+#[cfg(feature = "aio")]
+mod iocb_ext {
+    use super::*;
+
+    /// The flag in `aio_flags` that makes the kernel use `aio_reqprio` as the I/O priority.
+    const IOCB_FLAG_IOPRIO: u32 = 1 << 1;
+
+    mod private {
+        pub trait Sealed {}
+    }
+    impl private::Sealed for libc::iocb {}
+
+    /// An extension trait for the Linux AIO control block [`libc::iocb`], that allows retrieving
+    /// and setting the I/O priority of each individual request.
+    ///
+    /// The kernel only honors the priority of a control block when the `IOCB_FLAG_IOPRIO` flag is
+    /// set, which requires Linux 4.18 or later; older kernels reject control blocks with unknown
+    /// flags with `EINVAL` when submitted. Control blocks from other crates, such as those of
+    /// `libaio` bindings, share the same layout as the kernel ABI, and can thus be cast to this
+    /// type.
+    pub trait IocbExt: private::Sealed {
+        /// Get the priority of the control block, or [`None`] if `IOCB_FLAG_IOPRIO` is not set, in
+        /// which case the priority of the submitting thread is used.
+        fn priority(&self) -> Option<Priority>;
+        /// Set the priority of the control block, by storing the mask in `aio_reqprio` and setting
+        /// `IOCB_FLAG_IOPRIO`.
+        fn set_priority(&mut self, priority: Priority);
+        /// Clear `IOCB_FLAG_IOPRIO`, so that the priority of the submitting thread is used again.
+        fn clear_priority(&mut self);
+    }
+    impl IocbExt for libc::iocb {
+        fn priority(&self) -> Option<Priority> {
+            if self.aio_flags & IOCB_FLAG_IOPRIO == 0 {
+                return None;
+            }
+            Some(Priority {
+                inner: self.aio_reqprio as u16,
+            })
+        }
+        fn set_priority(&mut self, priority: Priority) {
+            self.aio_reqprio = priority.inner as i16;
+            self.aio_flags |= IOCB_FLAG_IOPRIO;
+        }
+        fn clear_priority(&mut self) {
+            self.aio_flags &= !IOCB_FLAG_IOPRIO;
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn control_blocks_round_trip_their_priority() {
+            let mut iocb: libc::iocb = unsafe { core::mem::zeroed() };
+            assert_eq!(iocb.priority(), None);
+
+            iocb.set_priority(Priority::idle());
+            assert_eq!(iocb.priority(), Some(Priority::idle()));
+            assert_eq!(iocb.aio_reqprio as u16, Priority::idle().inner());
+            assert_ne!(iocb.aio_flags & IOCB_FLAG_IOPRIO, 0);
+
+            iocb.clear_priority();
+            assert_eq!(iocb.priority(), None);
+            assert_eq!(iocb.aio_flags, 0);
+        }
+    }
+}
+#[cfg(feature = "aio")]
+pub use iocb_ext::IocbExt;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn error_round_trips_through_errno() {
+        let errnos = [
+            Errno::EPERM,
+            Errno::ESRCH,
+            Errno::EINVAL,
+            Errno::EIO,
+            Errno::UnknownErrno,
+        ];
+        for &errno in &errnos {
+            assert_eq!(Error::from(errno).raw(), errno);
+        }
+        assert_eq!(Error::from(Errno::EPERM), Error::PermissionDenied);
+        assert_eq!(Error::from(Errno::ESRCH), Error::InvalidTarget);
+        assert_eq!(Error::from(Errno::EINVAL), Error::InvalidPriority);
+        assert_eq!(Error::from(Errno::EIO), Error::Other(Errno::EIO));
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn error_converts_from_and_into_nix_errors() {
+        let errors = [
+            Error::PermissionDenied,
+            Error::InvalidTarget,
+            Error::InvalidPriority,
+            Error::Other(Errno::EIO),
+        ];
+        for &error in &errors {
+            assert_eq!(Error::from(nix::Error::from(error)), error);
+        }
+        assert_eq!(
+            Error::from(nix::Error::InvalidPath),
+            Error::Other(Errno::UnknownErrno)
+        );
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn syscalls_return_classified_errors() {
+        let dead = Target::Process(Pid::from_raw(i32::MAX));
+
+        assert_eq!(get_priority(dead), Err(Error::InvalidTarget));
+        assert_eq!(
+            set_priority(Target::current_thread(), Priority::from_inner(0xFFFF)),
+            Err(Error::InvalidPriority)
+        );
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn spawn_inheriting_current_restores_the_calling_thread() {
+        let before = get_current_priority().unwrap();
+        let handle = spawn_inheriting_current(Priority::idle(), get_current_priority).unwrap();
+
+        assert_eq!(get_current_priority(), Ok(before));
+        assert_eq!(handle.join().unwrap(), Ok(Priority::idle()));
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn can_set_requires_capabilities_for_realtime() {
+        let admin = 1 << Capability::SysAdmin.number();
+        let nice = 1 << Capability::SysNice.number();
+        let realtime = Priority::realtime(0).unwrap();
+        let idle = Priority::idle();
+
+        assert!(!can_set_with(0, false, realtime));
+        assert!(can_set_with(admin, false, realtime));
+        assert!(can_set_with(nice, false, realtime));
+        assert!(can_set_with(admin | nice, false, realtime));
+
+        assert!(!can_set_with(admin, true, realtime));
+        assert!(can_set_with(nice, true, realtime));
+
+        assert!(can_set_with(0, false, idle));
+        assert!(!can_set_with(0, true, idle));
+        assert!(!can_set_with(admin, true, idle));
+        assert!(can_set_with(nice, true, idle));
+    }
+    #[test]
+    fn levels_order_strictly_within_a_class() {
+        for level in 0..7 {
+            let higher = Priority::realtime(level).unwrap();
+            let lower = Priority::realtime(level + 1).unwrap();
+            assert!(higher > lower);
+
+            let higher = Priority::best_effort(level).unwrap();
+            let lower = Priority::best_effort(level + 1).unwrap();
+            assert!(higher > lower);
+        }
+        assert!(Priority::realtime(7).unwrap() > Priority::best_effort(0).unwrap());
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn priorities_sort_by_class_then_level() {
+        let mut priorities = Priority::all().rev().collect::<Vec<_>>();
+        priorities.sort();
+
+        let mut expected = vec![Priority::idle(), Priority::standard()];
+        expected.extend(
+            (0..8)
+                .rev()
+                .map(|level| Priority::best_effort(level).unwrap()),
+        );
+        expected.extend((0..8).rev().map(|level| Priority::realtime(level).unwrap()));
+
+        assert_eq!(priorities, expected);
+        assert!(Priority::from_inner(0xFFFF) < Priority::idle());
+        assert!(Priority::from_inner(Priority::prio_value(1, 8)) < Priority::idle());
+    }
+    #[test]
+    fn exactly_the_documented_masks_are_valid() {
+        assert!(Priority::is_valid(0));
+        assert_eq!(Priority::try_from(0), Ok(Priority::standard()));
+
+        for class in 4..8 {
+            assert!(!Priority::is_valid(Priority::prio_value(class, 0)));
+        }
+        for &class in &[1, 2] {
+            assert!(Priority::is_valid(Priority::prio_value(class, 7)));
+            assert!(!Priority::is_valid(Priority::prio_value(class, 8)));
+        }
+        for &class in &[0, 3] {
+            assert!(Priority::is_valid(Priority::prio_value(class, 0)));
+            assert!(!Priority::is_valid(Priority::prio_value(class, 1)));
+        }
+
+        let mut valid = 0;
+
+        for mask in 0..=u16::MAX {
+            assert_eq!(Priority::try_from(mask).is_ok(), Priority::is_valid(mask));
+            valid += usize::from(Priority::is_valid(mask));
+        }
+        assert_eq!(valid, 18);
+    }
+    #[test]
+    fn invalid_masks_report_their_reason() {
+        let reason = |mask: u16| Priority::try_from(mask).unwrap_err().reason();
+
+        for class in 4..8 {
+            assert_eq!(
+                reason(Priority::prio_value(class, 0)),
+                InvalidMaskReason::UnknownClass
+            );
+        }
+        for &class in &[1, 2] {
+            for &data in &[8, 0x1FFF] {
+                assert_eq!(
+                    reason(Priority::prio_value(class, data)),
+                    InvalidMaskReason::LevelOutOfRange
+                );
+            }
+        }
+        for &class in &[0, 3] {
+            assert_eq!(
+                reason(Priority::prio_value(class, 1)),
+                InvalidMaskReason::UnexpectedData
+            );
+        }
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn thread_targets_only_affect_that_thread() {
+        use std::sync::mpsc;
+
+        let (tid_sender, tid_receiver) = mpsc::channel();
+        let (done_sender, done_receiver) = mpsc::channel::<()>();
+
+        let handle = thread::spawn(move || {
+            tid_sender.send(gettid()).unwrap();
+            done_receiver.recv().unwrap();
+            get_current_priority()
+        });
+        let tid = tid_receiver.recv().unwrap();
+
+        set_current_priority(Priority::best_effort(3).unwrap()).unwrap();
+        set_priority(Target::Thread(tid), Priority::idle()).unwrap();
+
+        assert_eq!(get_priority(Target::Thread(tid)), Ok(Priority::idle()));
+        assert_eq!(
+            get_current_priority().unwrap(),
+            Priority::best_effort(3).unwrap()
+        );
+
+        done_sender.send(()).unwrap();
+        assert_eq!(handle.join().unwrap(), Ok(Priority::idle()));
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn next_urgency_walks_every_priority_once() {
+        let mut visited = Vec::new();
+        let mut current = Some(Priority::MAX);
+
+        while let Some(priority) = current {
+            visited.push(priority);
+            current = priority.next_less_urgent();
+        }
+        assert_eq!(visited.len(), Priority::all().len());
+
+        for priority in Priority::all() {
+            assert_eq!(
+                visited.iter().filter(|&&other| other == priority).count(),
+                1
+            );
+        }
+        assert_eq!(visited.last(), Some(&Priority::MIN));
+        assert_eq!(Priority::MIN.next_less_urgent(), None);
+        assert_eq!(Priority::MAX.next_more_urgent(), None);
+
+        let mut current = Some(Priority::MIN);
+
+        for &expected in visited.iter().rev() {
+            assert_eq!(current, Some(expected));
+            current = expected.next_more_urgent();
+        }
+        assert_eq!(current, None);
+        assert_eq!(Priority::from_inner(0xFFFF).next_less_urgent(), None);
+    }
+    #[test]
+    fn bytes_round_trip_in_both_orders() {
+        for priority in Priority::all() {
+            assert_eq!(
+                Priority::from_be_bytes(priority.to_be_bytes()),
+                Ok(priority)
+            );
+            assert_eq!(
+                Priority::from_le_bytes(priority.to_le_bytes()),
+                Ok(priority)
+            );
+        }
+        let priority = Priority::realtime(2).unwrap();
+
+        assert_eq!(priority.to_be_bytes(), [0x20, 0x02]);
+        assert_eq!(priority.to_le_bytes(), [0x02, 0x20]);
+        assert!(Priority::from_be_bytes([0xFF, 0xFF]).is_err());
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn total_sort_key_sorts_deterministically() {
+        let mut sorted = Priority::all().collect::<Vec<_>>();
+        sorted.sort_by_key(Priority::total_sort_key);
+
+        for pair in sorted.windows(2) {
+            assert!(pair[0].total_sort_key() < pair[1].total_sort_key());
+        }
+        for seed in 1..=16u64 {
+            let mut shuffled = Priority::all().collect::<Vec<_>>();
+            let mut state = seed;
+
+            // A Fisher-Yates shuffle, driven by a linear congruential generator.
+            for i in (1..shuffled.len()).rev() {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                shuffled.swap(i, (state >> 33) as usize % (i + 1));
+            }
+            shuffled.sort_by_key(Priority::total_sort_key);
+
+            assert_eq!(shuffled, sorted);
+        }
+    }
+    #[test]
+    fn raising_and_lowering_levels_saturates() {
+        assert_eq!(
+            RtPriorityLevel::highest().raise(),
+            RtPriorityLevel::highest()
+        );
+        assert_eq!(RtPriorityLevel::lowest().lower(), RtPriorityLevel::lowest());
+        assert_eq!(
+            RtPriorityLevel::lowest().raise_by(255),
+            RtPriorityLevel::highest()
+        );
+        assert_eq!(
+            RtPriorityLevel::highest().lower_by(255),
+            RtPriorityLevel::lowest()
+        );
+
+        assert_eq!(
+            BePriorityLevel::highest().raise(),
+            BePriorityLevel::highest()
+        );
+        assert_eq!(BePriorityLevel::lowest().lower(), BePriorityLevel::lowest());
+        assert_eq!(
+            BePriorityLevel::lowest().raise_by(255),
+            BePriorityLevel::highest()
+        );
+        assert_eq!(
+            BePriorityLevel::highest().lower_by(255),
+            BePriorityLevel::lowest()
+        );
+
+        assert_eq!(BePriorityLevel::fallback().raise().level(), 3);
+        assert_eq!(BePriorityLevel::fallback().lower().level(), 5);
+    }
+    #[test]
+    fn all_yields_every_valid_priority() {
+        assert_eq!(Priority::all().len(), 18);
+        assert_eq!(Priority::all().rev().len(), 18);
+
+        for priority in Priority::all() {
+            let class = priority
+                .class()
+                .expect("every priority decodes into a class");
+            assert_eq!(Priority::new(class), priority);
+        }
+        assert!(Priority::all().eq(ALL_PRIORITIES.iter().copied()));
+    }
+    #[test]
+    fn effective_resolves_none_from_the_nice_value() {
+        let standard = Priority::standard();
+
+        assert_eq!(standard.effective(-20), Priority::best_effort(0).unwrap());
+        assert_eq!(standard.effective(0), Priority::best_effort(4).unwrap());
+        assert_eq!(standard.effective(19), Priority::best_effort(7).unwrap());
+        assert_eq!(
+            standard.effective(i32::MIN),
+            Priority::best_effort(0).unwrap()
+        );
+        assert_eq!(
+            standard.effective(i32::MAX),
+            Priority::best_effort(7).unwrap()
+        );
+
+        for priority in Priority::all().filter(|&priority| priority != standard) {
+            assert_eq!(priority.effective(-20), priority);
+        }
+    }
+    fn check_priority_level<L: PriorityLevel>() {
+        assert_eq!(L::highest().level(), 0);
+        assert_eq!(L::lowest().level(), 7);
+        assert!(L::highest() > L::lowest());
+
+        for level in 0..8 {
+            assert_eq!(L::from_level(level).map(L::level), Some(level));
+        }
+        for level in 0..7 {
+            assert!(L::from_level(level) > L::from_level(level + 1));
+        }
+        assert_eq!(L::from_level(8), None);
+    }
+    #[test]
+    fn priority_level_is_implemented_by_both_level_types() {
+        check_priority_level::<RtPriorityLevel>();
+        check_priority_level::<BePriorityLevel>();
+    }
+    #[test]
+    fn min_and_max_bound_every_priority() {
+        assert!(Priority::MIN < Priority::MAX);
+        assert_eq!(Priority::MIN, Priority::new(Class::lowest()));
+        assert_eq!(Priority::MAX, Priority::new(Class::highest()));
+
+        for priority in Priority::all() {
+            assert!(Priority::MIN <= priority && priority <= Priority::MAX);
+        }
+        assert_eq!(Priority::all().min(), Some(Priority::MIN));
+        assert_eq!(Priority::all().max(), Some(Priority::MAX));
+    }
+    #[test]
+    fn ionice_arguments_are_accepted_or_rejected() {
+        let accepted = [
+            ((0, 0), Priority::standard()),
+            ((1, 0), Priority::realtime(0).unwrap()),
+            ((1, 7), Priority::realtime(7).unwrap()),
+            ((2, 4), Priority::best_effort(4).unwrap()),
+            ((2, 7), Priority::best_effort(7).unwrap()),
+            ((3, 0), Priority::idle()),
+        ];
+        for &((class, level), priority) in &accepted {
+            assert_eq!(Priority::from_ionice(class, level), Ok(priority));
+            assert_eq!(priority.to_ionice(), (class, level));
+        }
+
+        let rejected = [(0, 1), (3, 4), (1, 8), (2, 8), (4, 0), (255, 255)];
+
+        for &(class, level) in &rejected {
+            let error = Priority::from_ionice(class, level).unwrap_err();
+            assert_eq!((error.class(), error.level()), (class, level));
+        }
+        for priority in Priority::all() {
+            let (class, level) = priority.to_ionice();
+            assert_eq!(Priority::from_ionice(class, level), Ok(priority));
+        }
+    }
+    #[test]
+    fn from_inner_lossy_normalizes_malformed_masks() {
+        let lossy = |class, data| Priority::from_inner_lossy(Priority::prio_value(class, data));
+
+        assert_eq!(lossy(1, 8), Priority::realtime(7).unwrap());
+        assert_eq!(lossy(2, 8), Priority::best_effort(7).unwrap());
+        assert_eq!(lossy(2, 0x1FFF), Priority::best_effort(7).unwrap());
+        assert_eq!(lossy(3, 1), Priority::idle());
+        assert_eq!(lossy(0, 5), Priority::standard());
+
+        for class in 4..8 {
+            assert_eq!(lossy(class, 0), Priority::standard());
+            assert_eq!(lossy(class, 3), Priority::standard());
+        }
+        for priority in Priority::all() {
+            assert_eq!(Priority::from_inner_lossy(priority.inner()), priority);
+        }
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn with_priority_restores_after_returning_and_panicking() {
+        use std::panic;
+
+        let target = Target::current_process();
+        let before = Priority::best_effort(3).unwrap();
+        set_current_priority(before).unwrap();
+
+        let inside = with_priority(target, Priority::idle(), get_current_priority).unwrap();
+
+        assert_eq!(inside, Ok(Priority::idle()));
+        assert_eq!(get_current_priority(), Ok(before));
+
+        let result = panic::catch_unwind(|| {
+            with_priority(target, Priority::idle(), || panic!("inside with_priority"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(get_current_priority(), Ok(before));
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn reset_priority_restores_the_standard_priority() {
+        let target = Target::current_process();
+
+        set_priority(target, Priority::idle()).unwrap();
+        assert_eq!(get_current_priority(), Ok(Priority::idle()));
+
+        reset_priority(target).unwrap();
+        assert_eq!(get_restorable_priority(target), Ok(Priority::standard()));
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn set_priority_bulk_continues_past_exited_processes() {
+        let current = Target::Thread(gettid());
+        let dead = Target::Process(Pid::from_raw(i32::MAX));
+
+        let results = set_priority_bulk(&[current, dead], Priority::idle());
+
+        assert_eq!(
+            results,
+            vec![(current, Ok(())), (dead, Err(Error::InvalidTarget))]
+        );
+        assert_eq!(get_current_priority(), Ok(Priority::idle()));
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn get_priorities_records_exited_processes() {
+        set_current_priority(Priority::idle()).unwrap();
+
+        let current = gettid();
+        let dead = Pid::from_raw(i32::MAX);
+
+        assert_eq!(
+            get_priorities(&[current, dead]),
+            vec![
+                (current, Ok(Priority::idle())),
+                (dead, Err(Error::InvalidTarget))
+            ]
+        );
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn prioritized_threads_report_their_own_priority() {
+        let before = get_current_priority().unwrap();
+        let handle = PrioritizedThread::new(Priority::idle())
+            .name("prioritized".to_owned())
+            .spawn(|| {
+                (
+                    thread::current().name().map(str::to_owned),
+                    get_current_priority(),
+                )
+            })
+            .unwrap();
+
+        let (name, priority) = handle.join().unwrap().unwrap();
+
+        assert_eq!(name.as_deref(), Some("prioritized"));
+        assert_eq!(priority, Ok(Priority::idle()));
+        assert_eq!(get_current_priority(), Ok(before));
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn get_priority_raw_matches_get_priority() {
+        let current = Target::current_thread();
+        set_priority(current, Priority::best_effort(6).unwrap()).unwrap();
+
+        assert_eq!(
+            get_priority_raw(current),
+            Ok(i64::from(get_priority(current).unwrap().inner()))
+        );
+        assert_eq!(
+            get_priority_raw(Target::Process(Pid::from_raw(i32::MAX))),
+            Err(Error::InvalidTarget)
+        );
+    }
+    #[test]
+    fn percentages_round_trip_at_representative_values() {
+        for &include_realtime in &[false, true] {
+            for &percent in &[0, 25, 50, 75, 100] {
+                let priority = Priority::from_percent(percent, include_realtime);
+                assert_eq!(priority.to_percent(include_realtime), Some(percent));
+            }
+        }
+        assert_eq!(Priority::from_percent(0, true), Priority::idle());
+        assert_eq!(Priority::from_percent(100, true), Priority::MAX);
+        assert_eq!(
+            Priority::from_percent(100, false),
+            Priority::best_effort(0).unwrap()
+        );
+        assert_eq!(
+            Priority::from_percent(33, true),
+            Priority::best_effort(3).unwrap()
+        );
+        assert_eq!(Priority::from_percent(33, true).to_percent(true), Some(31));
+
+        for priority in Priority::all().filter(|&priority| priority != Priority::standard()) {
+            let percent = priority.to_percent(true).unwrap();
+            assert_eq!(Priority::from_percent(percent, true), priority);
+
+            if !priority.is_starvation_risk() {
+                let percent = priority.to_percent(false).unwrap();
+                assert_eq!(Priority::from_percent(percent, false), priority);
+            }
+        }
+    }
+    #[test]
+    fn conservative_merge_picks_the_less_urgent_priority() {
+        let realtime = Priority::realtime(0).unwrap();
+        let best_effort = Priority::best_effort(7).unwrap();
+        let fallback = Priority::best_effort(4).unwrap();
+        let unknown = Priority::from_inner(0xFFFF);
+
+        assert_eq!(
+            Priority::conservative_merge(realtime, best_effort),
+            best_effort
+        );
+        assert_eq!(
+            Priority::conservative_merge(best_effort, realtime),
+            best_effort
+        );
+        assert_eq!(
+            Priority::conservative_merge(best_effort, Priority::idle()),
+            Priority::idle()
+        );
+        assert_eq!(
+            Priority::conservative_merge(Priority::idle(), best_effort),
+            Priority::idle()
+        );
+
+        assert_eq!(
+            Priority::conservative_merge(Priority::standard(), fallback),
+            Priority::standard()
+        );
+        assert_eq!(
+            Priority::conservative_merge(fallback, Priority::standard()),
+            fallback
+        );
+
+        assert_eq!(Priority::conservative_merge(unknown, realtime), realtime);
+        assert_eq!(Priority::conservative_merge(realtime, unknown), realtime);
+        assert_eq!(
+            Priority::conservative_merge(unknown, Priority::from_inner(0x8000)),
+            unknown
+        );
+    }
+    #[test]
+    fn levels_convert_from_u8_up_to_seven() {
+        let rt: Result<RtPriorityLevel, _> = 7u8.try_into();
+        let be: Result<BePriorityLevel, _> = 7u8.try_into();
+
+        assert_eq!(rt, Ok(RtPriorityLevel::lowest()));
+        assert_eq!(be, Ok(BePriorityLevel::lowest()));
+
+        let rt: Result<RtPriorityLevel, _> = 8u8.try_into();
+        let be: Result<BePriorityLevel, _> = 8u8.try_into();
+
+        assert_eq!(rt, Err(LevelOutOfRange { level: 8 }));
+        assert_eq!(be, Err(LevelOutOfRange { level: 8 }));
+    }
+    #[test]
+    fn classes_round_trip_through_priorities() {
+        for priority in Priority::all() {
+            let class = priority.class().unwrap();
+            let converted: Priority = class.into();
+
+            assert_eq!(converted, priority);
+            assert_eq!(Class::try_from(converted), Ok(class));
+        }
+        for &mask in &[4, 0xFFFF] {
+            let error = Class::try_from(Priority::from_inner(mask)).unwrap_err();
+            assert_eq!(error.mask(), mask);
+        }
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn targets_map_to_their_documented_which_values() {
+        let pid = Pid::from_raw(42);
+        let uid = Uid::from_raw(1000);
+
+        let expected = [
+            (Target::Process(pid), 1, Some(pid), None),
+            (Target::Thread(pid), 1, Some(pid), None),
+            (Target::ProcessGroup(pid), 2, Some(pid), None),
+            (Target::User(uid), 3, None, Some(uid)),
+        ];
+        for &(target, which, as_pid, as_uid) in &expected {
+            assert_eq!(target.raw_which(), which);
+            assert_eq!(target.as_pid(), as_pid);
+            assert_eq!(target.as_uid(), as_uid);
+        }
+        assert_eq!(Target::Thread(pid).raw_who(), 42);
+        assert_eq!(Target::User(uid).raw_who(), 1000);
+    }
+    #[test]
+    fn sqe_bytes_hold_the_priority_at_offset_two() {
+        let priority = Priority::realtime(2).unwrap();
+        let mut sqe = [0; SQE_SIZE];
+
+        priority.write_to_sqe_bytes(&mut sqe);
+
+        assert_eq!(sqe[2..4], 0x2002u16.to_ne_bytes());
+        assert!(sqe[..2].iter().chain(&sqe[4..]).all(|&byte| byte == 0));
+        assert_eq!(Priority::read_from_sqe_bytes(&sqe), priority);
+
+        let mut sqe128 = [0xFF; 2 * SQE_SIZE];
+        Priority::idle().write_to_sqe_bytes(&mut sqe128);
+
+        assert_eq!(Priority::read_from_sqe_bytes(&sqe128), Priority::idle());
+        assert!(sqe128[..2]
+            .iter()
+            .chain(&sqe128[4..])
+            .all(|&byte| byte == 0xFF));
+    }
+    #[test]
+    #[should_panic]
+    fn short_sqe_buffers_are_rejected() {
+        Priority::idle().write_to_sqe_bytes(&mut [0; SQE_SIZE - 1]);
+    }
+    #[test]
+    fn class_codes_round_trip() {
+        let codes = [
+            (Class::Realtime(RtPriorityLevel::highest()), "rt"),
+            (Class::BestEffort(BePriorityLevel::lowest()), "be"),
+            (Class::Idle, "idle"),
+            (Class::None, "none"),
+        ];
+        for &(class, code) in &codes {
+            assert_eq!(class.as_code(), code);
+            assert_eq!(Class::from_code(code).map(Class::as_code), Some(code));
+        }
+        for priority in Priority::all() {
+            let class = priority.class().unwrap();
+            let parsed = Class::from_code(class.as_code()).unwrap();
+
+            assert_eq!(parsed.raw_kind(), class.raw_kind());
+        }
+        assert_eq!(Class::from_code("RT"), None);
+        assert_eq!(Class::from_code("realtime"), None);
+    }
+    #[test]
+    fn constructors_validate_their_level() {
+        for level in 0..8 {
+            let rt = RtPriorityLevel::from_level(level).unwrap();
+            let be = BePriorityLevel::from_level(level).unwrap();
+
+            assert_eq!(
+                Priority::realtime(level),
+                Ok(Priority::new(Class::Realtime(rt)))
+            );
+            assert_eq!(
+                Priority::best_effort(level),
+                Ok(Priority::new(Class::BestEffort(be)))
+            );
+        }
+        assert_eq!(Priority::realtime(8), Err(LevelOutOfRange { level: 8 }));
+        assert_eq!(Priority::best_effort(8), Err(LevelOutOfRange { level: 8 }));
+        assert_eq!(Priority::best_effort(u8::MAX).unwrap_err().level(), u8::MAX);
+        assert_eq!(Priority::idle(), Priority::new(Class::Idle));
+        assert_eq!(Priority::idle().class(), Some(Class::Idle));
+    }
+    #[test]
+    fn standard_priorities_compare_by_nice_value() {
+        let standard = Priority::standard();
+
+        assert_eq!(standard.cmp_effective(standard, -20, 19), Ordering::Greater);
+        assert_eq!(standard.cmp_effective(standard, 19, -20), Ordering::Less);
+        assert_eq!(standard.cmp_effective(standard, 0, 4), Ordering::Equal);
+
+        let be3 = Priority::best_effort(3).unwrap();
+
+        // Unlike `Ord`, which ranks the standard priority below every best-effort level.
+        assert_eq!(standard.cmp(&be3), Ordering::Less);
+        assert_eq!(standard.cmp_effective(be3, -20, 0), Ordering::Greater);
+        assert_eq!(standard.cmp_effective(be3, 0, 0), Ordering::Less);
+        assert_eq!(be3.cmp_effective(standard, 19, 0), Ordering::Greater);
+        assert_eq!(
+            standard.cmp_effective(Priority::best_effort(4).unwrap(), 0, 19),
+            Ordering::Equal
+        );
+    }
+    #[test]
+    fn classes_have_their_raw_kind_and_data() {
+        assert_eq!((Class::None.raw_kind(), Class::None.raw_data()), (0, 0));
+        assert_eq!((Class::Idle.raw_kind(), Class::Idle.raw_data()), (3, 0));
+
+        for level in 0..8 {
+            let rt = Class::Realtime(RtPriorityLevel::from_level(level).unwrap());
+            let be = Class::BestEffort(BePriorityLevel::from_level(level).unwrap());
+
+            assert_eq!((rt.raw_kind(), rt.raw_data()), (1, u16::from(level)));
+            assert_eq!((be.raw_kind(), be.raw_data()), (2, u16::from(level)));
+        }
+        for &priority in ALL_PRIORITIES {
+            let class = priority.class().unwrap();
+
+            assert_eq!(
+                Priority::prio_value(class.raw_kind(), class.raw_data()),
+                priority.inner()
+            );
+        }
+    }
 }
-#[cfg(any(doc, feature = "iou"))]
-pub use sqe_ext::SqeExt;
@@ -0,0 +1,252 @@
+//! Getting and setting priorities from async code, by running the syscalls on a separate thread.
+use std::cell::UnsafeCell;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, Once};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use super::*;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The worker thread that runs every offloaded closure in order, which is started on first use,
+/// and then kept alive for the rest of the process.
+struct Worker {
+    init: Once,
+    sender: UnsafeCell<Option<Mutex<Sender<Job>>>>,
+}
+// SAFETY: The sender is only written once, from within `init`, which synchronizes with every
+// subsequent read.
+unsafe impl Sync for Worker {}
+
+static WORKER: Worker = Worker {
+    init: Once::new(),
+    sender: UnsafeCell::new(None),
+};
+
+impl Worker {
+    fn submit(&self, job: Job) {
+        self.init.call_once(|| {
+            let (sender, receiver) = mpsc::channel::<Job>();
+
+            thread::Builder::new()
+                .name("ioprio-offload".to_owned())
+                .spawn(move || {
+                    for job in receiver {
+                        job();
+                    }
+                })
+                .expect("failed to spawn the I/O priority worker thread");
+
+            // SAFETY: This is the only write, and no reads can happen before `init` completes.
+            unsafe { *self.sender.get() = Some(Mutex::new(sender)) };
+        });
+        // SAFETY: The sender is never written again once `init` has completed.
+        let sender = unsafe { &*self.sender.get() }
+            .as_ref()
+            .expect("the I/O priority worker thread failed to start");
+
+        sender
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .send(job)
+            .expect("the I/O priority worker thread has exited");
+    }
+}
+
+struct Shared<T> {
+    result: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+impl<T> fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shared")
+            .field("done", &self.result.is_some())
+            .finish()
+    }
+}
+
+/// A future resolving to the result of a closure that runs on a separate thread, as returned by
+/// [`get_priority_async`] and [`set_priority_async`].
+///
+/// The closure is queued as soon as the future is created, rather than when it is first polled,
+/// and runs even if the future is dropped. If the closure panics, the panic is propagated when
+/// polling the future.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled, although the syscall is issued regardless"]
+pub struct Offloaded<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+impl<T: Send + 'static> Offloaded<T> {
+    fn spawn<F>(f: F) -> Self
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let shared = Arc::new(Mutex::new(Shared {
+            result: None,
+            waker: None,
+        }));
+        let remote = Arc::clone(&shared);
+
+        WORKER.submit(Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f));
+            let mut shared = remote.lock().unwrap_or_else(|error| error.into_inner());
+
+            shared.result = Some(result);
+
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }));
+        Self { shared }
+    }
+}
+impl<T> Future for Offloaded<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut shared = self
+            .shared
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+
+        match shared.result.take() {
+            Some(Ok(value)) => Poll::Ready(value),
+            Some(Err(payload)) => panic::resume_unwind(payload),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Resolve a target referring to the calling thread by zero, to its TID, so that it keeps
+/// referring to the same thread when used from another one.
+fn resolve(target: Target) -> Target {
+    match target {
+        Target::Process(pid) | Target::Thread(pid) if pid.as_raw() == 0 => Target::current_thread(),
+        other => other,
+    }
+}
+
+/// Get the I/O priority of a target like [`get_priority`], but without blocking the calling thread.
+///
+/// The syscall is issued from a single worker thread shared by every call, which is started on
+/// first use, and which processes the calls one at a time in the order they were made. Since I/O
+/// priorities belong to individual threads, a [`Target::Process`] or [`Target::Thread`] with a PID
+/// of zero is first resolved to the TID of the calling thread, which would otherwise refer to the
+/// spawned thread instead. Note that on a multithreaded executor, this is whichever worker thread
+/// happens to call this function, which may not be the thread that runs the rest of the task, so
+/// targeting the calling thread is rarely meaningful from async code.
+///
+/// Since the calls are processed one at a time, a slow call delays every call queued after it,
+/// from any task or thread of the process. In particular, a [`Target::User`] makes the kernel
+/// walk every thread on the system, which can take a while on busy systems, and a call queued
+/// behind it cannot complete any sooner, however cheap it is by itself.
+///
+/// This works with any executor. Runtimes with a dedicated pool for blocking work, such as the
+/// `spawn_blocking` function of Tokio, can instead run [`get_priority`] on that pool directly,
+/// after resolving the target in the same way, which also avoids the calls delaying each other.
+pub fn get_priority_async(target: Target) -> Offloaded<Result<Priority>> {
+    let target = resolve(target);
+    Offloaded::spawn(move || get_priority(target))
+}
+/// Set the I/O priority of a target like [`set_priority`], but without blocking the calling thread.
+///
+/// Refer to [`get_priority_async`] for how the syscall is offloaded, including how slow calls delay
+/// later ones, and how targets referring to the calling thread are resolved.
+pub fn set_priority_async(target: Target, priority: Priority) -> Offloaded<Result<()>> {
+    let target = resolve(target);
+    Offloaded::spawn(move || set_priority(target, priority))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Wake;
+    use std::time::Duration;
+
+    struct ThreadWaker(thread::Thread);
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn offloaded_calls_target_the_calling_thread() {
+        let target = Target::current_process();
+
+        block_on(set_priority_async(target, Priority::idle())).unwrap();
+        assert_eq!(get_current_priority(), Ok(Priority::idle()));
+        assert_eq!(block_on(get_priority_async(target)), Ok(Priority::idle()));
+    }
+    #[test]
+    fn offloaded_calls_share_one_worker_thread() {
+        let first = block_on(Offloaded::spawn(|| thread::current().id()));
+        let second = block_on(Offloaded::spawn(|| thread::current().id()));
+
+        assert_eq!(first, second);
+        assert_ne!(first, thread::current().id());
+    }
+    #[test]
+    fn offloaded_panics_propagate_without_stopping_the_worker() {
+        let result = panic::catch_unwind(|| block_on(Offloaded::spawn(|| panic!("offloaded"))));
+
+        assert!(result.is_err());
+        assert_eq!(block_on(Offloaded::spawn(|| 1)), 1);
+    }
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn offloaded_calls_complete_on_tokio() {
+        let (target_sender, target_receiver) = mpsc::channel();
+        let (done_sender, done_receiver) = mpsc::channel::<()>();
+
+        let parked = thread::spawn(move || {
+            target_sender.send(Target::current_thread()).unwrap();
+            let _ = done_receiver.recv();
+        });
+        let target = target_receiver.recv().unwrap();
+
+        let task = tokio::spawn(async move {
+            set_priority_async(target, Priority::idle()).await?;
+            get_priority_async(target).await
+        });
+        assert_eq!(task.await.unwrap(), Ok(Priority::idle()));
+
+        drop(done_sender);
+        parked.join().unwrap();
+    }
+    #[tokio::test]
+    async fn slow_calls_delay_later_ones() {
+        let (release_sender, release_receiver) = mpsc::channel::<()>();
+
+        let slow = Offloaded::spawn(move || {
+            let _ = release_receiver.recv();
+        });
+        let mut later = get_priority_async(Target::current_process());
+
+        let early = tokio::time::timeout(Duration::from_millis(50), &mut later).await;
+        assert!(early.is_err());
+
+        drop(release_sender);
+        slow.await;
+        assert!(later.await.is_ok());
+    }
+}
@@ -0,0 +1,206 @@
+//! Parsing of textual priorities, such as `be:4`, `rt/0`, or `idle`.
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+use super::*;
+
+/// The error returned when parsing a textual priority fails.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ParseError {
+    /// The class name was not recognized.
+    UnknownClass(String),
+    /// The level was either not a number, or not in the range 0-7.
+    InvalidLevel(String),
+    /// A level was given for a class that does not have any levels (`idle` and `none`).
+    UnexpectedLevel(String),
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownClass(class) => write!(f, "unknown I/O priority class `{}`", class),
+            Self::InvalidLevel(level) => write!(f, "invalid I/O priority level `{}`", level),
+            Self::UnexpectedLevel(class) => {
+                write!(
+                    f,
+                    "the I/O priority class `{}` does not take a level",
+                    class
+                )
+            }
+        }
+    }
+}
+impl std::error::Error for ParseError {}
+
+impl FromStr for Class {
+    type Err = ParseError;
+
+    /// Parse a class from its name, optionally followed by a level separated by either `:` or `/`.
+    ///
+    /// The accepted class names are `rt` or `realtime`, `be` or `best-effort`, `idle`, and `none`,
+    /// all of which are case-insensitive. The real-time and best-effort classes take a level from 0
+    /// to 7, defaulting to 4 like `ionice` does, whereas the idle and none classes do not take any
+    /// level at all. The level may also be prefixed with `prio`, which makes the format produced
+    /// by the [`Display`](fmt::Display) implementation, such as `best-effort: prio 4`, parseable
+    /// as well.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (class, level) = match s.find(&[':', '/'][..]) {
+            Some(idx) => (s[..idx].trim(), Some(s[idx + 1..].trim())),
+            None => (s.trim(), None),
+        };
+        let is = |name: &str| class.eq_ignore_ascii_case(name);
+
+        let parse_level = || -> Result<u8, ParseError> {
+            match level {
+                Some(level) => {
+                    let digits = strip_prio(level).unwrap_or(level);
+                    digits
+                        .parse::<u8>()
+                        .ok()
+                        .filter(|&level| level < 8)
+                        .ok_or_else(|| ParseError::InvalidLevel(level.to_owned()))
+                }
+                None => Ok(BePriorityLevel::fallback().level()),
+            }
+        };
+
+        if is("rt") || is("realtime") {
+            let level = RtPriorityLevel::from_level(parse_level()?).expect("level checked above");
+            Ok(Class::Realtime(level))
+        } else if is("be") || is("best-effort") {
+            let level = BePriorityLevel::from_level(parse_level()?).expect("level checked above");
+            Ok(Class::BestEffort(level))
+        } else if is("idle") || is("none") {
+            if level.is_some() {
+                return Err(ParseError::UnexpectedLevel(class.to_owned()));
+            }
+            Ok(if is("idle") { Class::Idle } else { Class::None })
+        } else {
+            Err(ParseError::UnknownClass(class.to_owned()))
+        }
+    }
+}
+
+/// Strip the `prio` prefix of a level in the format produced by the [`Display`](fmt::Display)
+/// implementations, returning [`None`] if there is no such prefix.
+fn strip_prio(level: &str) -> Option<&str> {
+    match level.get(..4) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("prio") => Some(level[4..].trim()),
+        _ => None,
+    }
+}
+
+impl FromStr for Priority {
+    type Err = ParseError;
+
+    /// Parse a priority the same way as a [`Class`], with `none` being the
+    /// [standard](Priority::standard) priority.
+    ///
+    /// This accepts both the compact format, such as `be:4`, `rt/0`, or `idle`, and the format
+    /// produced by the [`Display`](fmt::Display) implementation. The latter includes masks of the
+    /// none class with a level, such as `none: prio 4`, which some kernels report for threads
+    /// without a class. Such a level is only accepted with the `prio` prefix, so that `none:4`
+    /// is still rejected. Thus, every priority that decodes into a class round-trips.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse() {
+            Ok(class) => Ok(Self::new(class)),
+            Err(ParseError::UnexpectedLevel(class)) if class.eq_ignore_ascii_case("none") => {
+                let idx = s.find(&[':', '/'][..]).expect("a level was given");
+
+                strip_prio(s[idx + 1..].trim())
+                    .and_then(|digits| digits.parse::<u16>().ok())
+                    .filter(|&level| level < 8)
+                    .map(|level| Self::from_inner(Self::prio_value(0, level)))
+                    .ok_or(ParseError::UnexpectedLevel(class))
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// The environment variable read by [`Priority::from_env`].
+pub const ENV_VAR: &str = "IO_PRIORITY";
+
+impl Priority {
+    /// Read a priority from the [`IO_PRIORITY`](ENV_VAR) environment variable, using the
+    /// [`FromStr`] implementation.
+    ///
+    /// This returns `Ok(None)` if the variable is unset, and an error if it is set to a malformed
+    /// value, leaving it up to the caller whether to abort, log, or fall back to a default.
+    pub fn from_env() -> Result<Option<Self>, ParseError> {
+        Self::from_env_var(ENV_VAR)
+    }
+    /// Read a priority from an arbitrary environment variable, like [`from_env`](Self::from_env).
+    ///
+    /// This returns `Ok(None)` if the variable is unset, and an error if it is set to a malformed
+    /// value. A value that is not valid Unicode is parsed lossily, and will thus fail to parse.
+    pub fn from_env_var(var: &str) -> Result<Option<Self>, ParseError> {
+        match env::var_os(var) {
+            Some(value) => value.to_string_lossy().parse().map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn display_round_trips_through_parsing() {
+        for priority in Priority::all() {
+            assert_eq!(priority.to_string().parse(), Ok(priority));
+        }
+        for level in 1..8 {
+            let priority = Priority::from_inner(Priority::prio_value(0, level));
+
+            assert_eq!(priority.to_string(), format!("none: prio {}", level));
+            assert_eq!(priority.to_string().parse(), Ok(priority));
+        }
+        assert_eq!(
+            "none:4".parse::<Priority>(),
+            Err(ParseError::UnexpectedLevel("none".to_owned()))
+        );
+    }
+    #[test]
+    fn levels_are_rejected_where_invalid() {
+        assert_eq!(
+            "rt:8".parse::<Priority>(),
+            Err(ParseError::InvalidLevel("8".to_owned()))
+        );
+        assert_eq!(
+            "idle:1".parse::<Priority>(),
+            Err(ParseError::UnexpectedLevel("idle".to_owned()))
+        );
+        assert_eq!(
+            "none/0".parse::<Priority>(),
+            Err(ParseError::UnexpectedLevel("none".to_owned()))
+        );
+    }
+    #[test]
+    fn unset_variables_yield_none() {
+        let var = "IOPRIO_TEST_UNSET";
+        env::remove_var(var);
+
+        assert_eq!(Priority::from_env_var(var), Ok(None));
+    }
+    #[test]
+    fn valid_variables_are_parsed() {
+        let var = "IOPRIO_TEST_VALID";
+        env::set_var(var, "be/6");
+
+        assert_eq!(
+            Priority::from_env_var(var),
+            Ok(Some(Priority::best_effort(6).unwrap()))
+        );
+    }
+    #[test]
+    fn malformed_variables_are_errors() {
+        let var = "IOPRIO_TEST_MALFORMED";
+        env::set_var(var, "fast");
+
+        assert_eq!(
+            Priority::from_env_var(var),
+            Err(ParseError::UnknownClass("fast".to_owned()))
+        );
+    }
+}
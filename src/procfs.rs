@@ -0,0 +1,154 @@
+//! Helpers for enumerating processes and reading their attributes through `/proc`.
+//!
+//! Everything here is inherently racy, as processes can be created or exit at any time. Processes
+//! that vanish while being inspected are reported as `ESRCH`, mirroring the syscalls.
+use std::fs;
+use std::io;
+
+use nix::errno::Errno;
+use nix::unistd::{getpgrp, Pid, Uid};
+
+use super::{Error, Result, Target};
+
+pub(crate) fn io_error(error: io::Error) -> Error {
+    match error.raw_os_error() {
+        Some(libc::ENOENT) => Error::InvalidTarget,
+        Some(raw) => Errno::from_i32(raw).into(),
+        None => Error::Other(Errno::EIO),
+    }
+}
+
+/// List the PIDs of all processes currently visible in `/proc`.
+pub(crate) fn pids() -> Result<Vec<Pid>> {
+    let mut pids = Vec::new();
+
+    for entry in fs::read_dir("/proc").map_err(io_error)? {
+        let entry = entry.map_err(io_error)?;
+
+        if let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse().ok())
+        {
+            pids.push(Pid::from_raw(pid));
+        }
+    }
+    Ok(pids)
+}
+
+/// Get the process group ID of a process, from the fifth field of `/proc/<pid>/stat`.
+pub(crate) fn process_group(pid: Pid) -> Result<Pid> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).map_err(io_error)?;
+
+    // The second field is the command name in parentheses, which may itself contain both spaces
+    // and parentheses, so skip past the last closing parenthesis first.
+    let rest = stat
+        .rfind(')')
+        .map(|idx| &stat[idx + 1..])
+        .ok_or(Error::from(Errno::EINVAL))?;
+
+    rest.split_whitespace()
+        .nth(2)
+        .and_then(|field| field.parse().ok())
+        .map(Pid::from_raw)
+        .ok_or(Error::from(Errno::EINVAL))
+}
+
+/// Get the real UID of a process, from the `Uid:` line of `/proc/<pid>/status`.
+pub(crate) fn real_uid(pid: Pid) -> Result<Uid> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).map_err(io_error)?;
+
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|uids| uids.split_whitespace().next())
+        .and_then(|uid| uid.parse().ok())
+        .map(Uid::from_raw)
+        .ok_or(Error::from(Errno::EINVAL))
+}
+
+/// Get the effective capability set of the calling thread, from the `CapEff:` line of
+/// `/proc/thread-self/status`.
+pub(crate) fn effective_capabilities() -> Result<u64> {
+    let status = fs::read_to_string("/proc/thread-self/status").map_err(io_error)?;
+
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))
+        .and_then(|caps| u64::from_str_radix(caps.trim(), 16).ok())
+        .ok_or(Error::from(Errno::EINVAL))
+}
+
+/// List the PIDs of all processes that are members of the given process group.
+///
+/// Processes that exit while being inspected are skipped.
+pub(crate) fn group_members(pgid: Pid) -> Result<Vec<Pid>> {
+    let mut members = Vec::new();
+
+    for pid in pids()? {
+        match process_group(pid) {
+            Ok(group) if group == pgid => members.push(pid),
+            Ok(_) | Err(Error::InvalidTarget) => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(members)
+}
+
+/// List the PIDs of all processes owned by the given user, by their real UID.
+///
+/// Processes that exit while being inspected are skipped.
+pub(crate) fn user_processes(uid: Uid) -> Result<Vec<Pid>> {
+    let mut processes = Vec::new();
+
+    for pid in pids()? {
+        match real_uid(pid) {
+            Ok(owner) if owner == uid => processes.push(pid),
+            Ok(_) | Err(Error::InvalidTarget) => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(processes)
+}
+
+/// List the thread IDs of all threads of a process, from `/proc/<pid>/task`.
+pub(crate) fn threads(pid: Pid) -> Result<Vec<Pid>> {
+    let mut threads = Vec::new();
+
+    for entry in fs::read_dir(format!("/proc/{}/task", pid)).map_err(io_error)? {
+        let entry = entry.map_err(io_error)?;
+
+        if let Some(tid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse().ok())
+        {
+            threads.push(Pid::from_raw(tid));
+        }
+    }
+    Ok(threads)
+}
+
+/// List the thread IDs of all threads matching a target, which are what the kernel iterates over
+/// when getting or setting the priority of a process group or user.
+///
+/// A single process or thread only ever refers to a single thread, and is returned as-is.
+/// Processes that exit while being inspected are skipped.
+pub(crate) fn target_threads(target: Target) -> Result<Vec<Pid>> {
+    let processes = match target {
+        Target::Process(pid) | Target::Thread(pid) => return Ok(vec![pid]),
+        Target::ProcessGroup(pgid) if pgid.as_raw() == 0 => group_members(getpgrp())?,
+        Target::ProcessGroup(pgid) => group_members(pgid)?,
+        Target::User(uid) => user_processes(uid)?,
+    };
+    let mut threads = Vec::new();
+
+    for pid in processes {
+        match self::threads(pid) {
+            Ok(tids) => threads.extend(tids),
+            Err(Error::InvalidTarget) => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(threads)
+}
@@ -0,0 +1,83 @@
+//! Generation of arbitrary priorities, classes and levels for property tests, using `proptest`.
+//!
+//! Only valid values are ever generated, so every generated [`Priority`] decodes into a [`Class`],
+//! and constructing a priority from that class again yields the same mask.
+use proptest_::arbitrary::Arbitrary;
+use proptest_::sample::{select, Select};
+use proptest_::strategy::{Map, Strategy};
+
+use super::*;
+
+/// Generates every [valid](Priority::is_valid) priority, including the
+/// [standard](Priority::standard) priority, shrinking in the order of [`ALL_PRIORITIES`].
+impl Arbitrary for Priority {
+    type Parameters = ();
+    type Strategy = Select<Priority>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        select(ALL_PRIORITIES)
+    }
+}
+/// Generates every class and level, including [`Class::None`], like the [`Arbitrary`]
+/// implementation of [`Priority`].
+impl Arbitrary for Class {
+    type Parameters = ();
+    type Strategy = Map<Select<Priority>, fn(Priority) -> Class>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        select(ALL_PRIORITIES).prop_map(|priority| priority.class().expect("valid priority"))
+    }
+}
+/// Generates every level from 0 to 7.
+impl Arbitrary for RtPriorityLevel {
+    type Parameters = ();
+    type Strategy = Map<core::ops::Range<u8>, fn(u8) -> RtPriorityLevel>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (0..8).prop_map(|level| Self::from_level(level).expect("level is in range"))
+    }
+}
+/// Generates every level from 0 to 7.
+impl Arbitrary for BePriorityLevel {
+    type Parameters = ();
+    type Strategy = Map<core::ops::Range<u8>, fn(u8) -> BePriorityLevel>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (0..8).prop_map(|level| Self::from_level(level).expect("level is in range"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest_::arbitrary::any;
+    use proptest_::test_runner::TestRunner;
+
+    #[test]
+    fn only_valid_values_are_generated() {
+        let mut runner = TestRunner::default();
+
+        runner
+            .run(&any::<Priority>(), |priority| {
+                assert!(Priority::is_valid(priority.inner()));
+                assert_eq!(Priority::new(priority.class().unwrap()), priority);
+                Ok(())
+            })
+            .unwrap();
+        runner
+            .run(&any::<Class>(), |class| {
+                assert_eq!(Priority::new(class).class(), Some(class));
+                Ok(())
+            })
+            .unwrap();
+        runner
+            .run(
+                &(any::<RtPriorityLevel>(), any::<BePriorityLevel>()),
+                |(rt, be)| {
+                    assert!(rt.level() < 8 && be.level() < 8);
+                    Ok(())
+                },
+            )
+            .unwrap();
+    }
+}
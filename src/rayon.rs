@@ -0,0 +1,66 @@
+//! Setting the I/O priority of the worker threads of `rayon` thread pools.
+//!
+//! Rayon moves jobs freely between the workers of a pool, so a job cannot meaningfully set the
+//! I/O priority of the thread it happens to run on, as that would instead affect every later job
+//! of that worker. The priority is thus set for the pool as a whole, by each worker as it starts,
+//! and applies to all I/O performed by its jobs. The thread building the pool, and any thread
+//! calling into it through methods such as `install`, keep their own priority.
+use rayon_::ThreadPoolBuilder;
+
+use super::*;
+
+mod private {
+    pub trait Sealed {}
+}
+impl<S> private::Sealed for ThreadPoolBuilder<S> {}
+
+/// An extension trait for [`rayon::ThreadPoolBuilder`](ThreadPoolBuilder), that allows building
+/// thread pools whose workers run with a specific I/O priority.
+pub trait ThreadPoolBuilderExt: private::Sealed + Sized {
+    /// Set the I/O priority of every worker thread as it starts, using
+    /// [`start_handler`](ThreadPoolBuilder::start_handler).
+    ///
+    /// This replaces any start handler set before, and is in turn replaced by any start handler
+    /// set afterwards. Setting the priority of a worker can fail, for instance if the real-time
+    /// class is requested without `CAP_SYS_ADMIN`, in which case the worker panics with the error
+    /// before running any jobs. Rayon passes such panics to the panic handler of the pool, and
+    /// aborts the process if there is none, so it is advisable to first try setting the priority
+    /// on the current thread, if the priority cannot be known to be permitted.
+    fn start_with_priority(self, priority: Priority) -> Self;
+}
+impl<S> ThreadPoolBuilderExt for ThreadPoolBuilder<S> {
+    fn start_with_priority(self, priority: Priority) -> Self {
+        self.start_handler(move |index| {
+            if let Err(error) = set_current_priority(priority) {
+                panic!(
+                    "failed to set the I/O priority of worker {}: {}",
+                    index, error
+                );
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workers_run_at_the_priority() {
+        let before = get_current_priority().unwrap();
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(3)
+            .start_with_priority(Priority::idle())
+            .build()
+            .unwrap();
+
+        let priorities = pool.broadcast(|_| get_current_priority());
+
+        assert_eq!(priorities.len(), 3);
+
+        for priority in priorities {
+            assert_eq!(priority, Ok(Priority::idle()));
+        }
+        assert_eq!(get_current_priority(), Ok(before));
+    }
+}
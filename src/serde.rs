@@ -0,0 +1,148 @@
+//! Serialization of priorities and their classes and levels, using `serde`.
+//!
+//! Human-readable formats, such as JSON or TOML, use the same text as the
+//! [`Display`](fmt::Display) and [`FromStr`] implementations, for example `best-effort: prio 4`,
+//! whereas other formats use the compact 16-bit mask. The levels are always represented by their
+//! number. Every value is validated when deserialized, so that invalid masks and levels are
+//! rejected rather than producing a priority that cannot be decoded.
+use std::fmt;
+use std::str::FromStr;
+
+use serde_::de::{self, Deserialize, Deserializer};
+use serde_::ser::{self, Serialize, Serializer};
+
+use super::*;
+
+/// Deserialize a value from its textual representation, using its [`FromStr`] implementation.
+fn deserialize_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(de::Error::custom)
+}
+
+/// Serializes the priority as text in human-readable formats, and as the inner mask otherwise.
+///
+/// Only [valid](Priority::is_valid) masks can be serialized, since they would otherwise fail to
+/// deserialize.
+impl Serialize for Priority {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let priority = Priority::try_from(self.inner).map_err(ser::Error::custom)?;
+
+        if serializer.is_human_readable() {
+            serializer.collect_str(&priority)
+        } else {
+            serializer.serialize_u16(priority.inner)
+        }
+    }
+}
+/// Deserializes the priority from either its text or its inner mask, failing unless it is
+/// [valid](Priority::is_valid).
+impl<'de> Deserialize<'de> for Priority {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mask = if deserializer.is_human_readable() {
+            deserialize_str::<_, Priority>(deserializer)?.inner
+        } else {
+            u16::deserialize(deserializer)?
+        };
+        Priority::try_from(mask).map_err(de::Error::custom)
+    }
+}
+
+/// Serializes the class as text in human-readable formats, and as the mask of the corresponding
+/// [`Priority`] otherwise.
+impl Serialize for Class {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Priority::new(*self).serialize(serializer)
+    }
+}
+/// Deserializes the class from either its text or the mask of the corresponding [`Priority`].
+impl<'de> Deserialize<'de> for Class {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Class::try_from(Priority::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+/// Serializes the level as its number, from 0 to 7.
+impl Serialize for RtPriorityLevel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.level())
+    }
+}
+/// Deserializes the level from its number, failing if it exceeds 7.
+impl<'de> Deserialize<'de> for RtPriorityLevel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::try_from(u8::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+/// Serializes the level as its number, from 0 to 7.
+impl Serialize for BePriorityLevel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.level())
+    }
+}
+/// Deserializes the level from its number, failing if it exceeds 7.
+impl<'de> Deserialize<'de> for BePriorityLevel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::try_from(u8::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_test::{
+        assert_de_tokens_error, assert_ser_tokens_error, assert_tokens, Compact, Configure,
+        Readable, Token,
+    };
+
+    #[test]
+    fn priorities_use_text_or_masks() {
+        let priority = Priority::best_effort(4).unwrap();
+
+        assert_tokens(&priority.readable(), &[Token::Str("best-effort: prio 4")]);
+        assert_tokens(&priority.compact(), &[Token::U16(0x4004)]);
+        assert_tokens(&Priority::standard().readable(), &[Token::Str("none")]);
+        assert_tokens(&Priority::idle().compact(), &[Token::U16(0x6000)]);
+
+        let class = Class::Realtime(RtPriorityLevel::highest());
+
+        assert_tokens(&class.readable(), &[Token::Str("realtime: prio 0")]);
+        assert_tokens(&class.compact(), &[Token::U16(0x2000)]);
+
+        assert_tokens(&RtPriorityLevel::lowest(), &[Token::U8(7)]);
+        assert_tokens(&BePriorityLevel::fallback(), &[Token::U8(4)]);
+    }
+    #[test]
+    fn invalid_data_is_rejected() {
+        assert_de_tokens_error::<Readable<Priority>>(
+            &[Token::Str("be:8")],
+            "invalid I/O priority level `8`",
+        );
+        assert_de_tokens_error::<Readable<Priority>>(
+            &[Token::Str("none: prio 4")],
+            "invalid I/O priority mask 0x0004: class 0 does not take a level, but got 4",
+        );
+        assert_de_tokens_error::<Compact<Priority>>(
+            &[Token::U16(0x8000)],
+            "invalid I/O priority mask 0x8000: unknown class 4",
+        );
+        assert_de_tokens_error::<Compact<Class>>(
+            &[Token::U16(0x2008)],
+            "invalid I/O priority mask 0x2008: level 8 is not in 0-7",
+        );
+        assert_de_tokens_error::<BePriorityLevel>(
+            &[Token::U8(8)],
+            "I/O priority level 8 is not in 0-7",
+        );
+        assert_ser_tokens_error(
+            &Priority::from_inner(0xFFFF).compact(),
+            &[],
+            "invalid I/O priority mask 0xffff: unknown class 7",
+        );
+    }
+}
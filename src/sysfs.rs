@@ -0,0 +1,380 @@
+//! Inspection of block devices and their I/O schedulers through sysfs.
+use std::fs;
+use std::io;
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use nix::errno::Errno;
+use nix::sys::stat::{fstat, major, minor};
+
+/// Resolve a link to the sysfs directory of a block device, to the directory of the whole disk if
+/// the device is a partition.
+fn whole_disk_dir(link: &Path) -> io::Result<PathBuf> {
+    let mut dir = fs::canonicalize(link)?;
+
+    if dir.join("partition").exists() {
+        dir.pop();
+    }
+    Ok(dir)
+}
+
+/// Get the sysfs directory of the whole disk of a block device, given either its name (`sda1`) or
+/// its device node (`/dev/sda1`), with sysfs mounted at `sys`.
+fn block_dir_in(sys: &Path, device: &Path) -> io::Result<PathBuf> {
+    // Device nodes are commonly symlinks, such as those in `/dev/mapper` or `/dev/disk`, whose
+    // names do not match the name of the device itself.
+    let node = if device.components().nth(1).is_some() {
+        fs::canonicalize(device)?
+    } else {
+        device.to_owned()
+    };
+    let name = node
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "missing device name"))?;
+
+    whole_disk_dir(&sys.join("class/block").join(name))
+}
+/// Get the sysfs directory of the whole disk of a block device, like [`block_dir_in`] with sysfs
+/// mounted at `/sys`.
+fn block_dir(device: &Path) -> io::Result<PathBuf> {
+    block_dir_in(Path::new("/sys"), device)
+}
+
+/// Read a sysfs attribute, returning [`None`] if it does not exist.
+fn read_attr(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Parse the active scheduler from the contents of a `queue/scheduler` file, which lists all
+/// available schedulers with the active one in brackets, e.g. `mq-deadline kyber [bfq] none`.
+pub(crate) fn parse_active_scheduler(contents: &str) -> Option<&str> {
+    if let Some(active) = contents
+        .split_whitespace()
+        .find_map(|name| name.strip_prefix('[')?.strip_suffix(']'))
+    {
+        return Some(active);
+    }
+    // Devices without any choice of scheduler only list that single scheduler, without brackets.
+    let mut schedulers = contents.split_whitespace();
+
+    match (schedulers.next(), schedulers.next()) {
+        (Some(only), None) => Some(only),
+        _ => None,
+    }
+}
+
+/// The tunables of the active I/O scheduler of a block device that affect how I/O priorities
+/// translate into actual behavior, as returned by [`read_iosched_tunables`].
+///
+/// Every tunable is [`None`] if the active scheduler does not have it, or if it could not be
+/// parsed. BFQ and the legacy CFQ scheduler share most of them, whereas schedulers such as
+/// mq-deadline have none of them.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct IoschedTunables {
+    scheduler: Option<String>,
+    low_latency: Option<bool>,
+    slice_idle: Option<Duration>,
+    strict_guarantees: Option<bool>,
+    timeout_sync: Option<Duration>,
+    slice_sync: Option<Duration>,
+    slice_async: Option<Duration>,
+    group_idle: Option<Duration>,
+}
+impl IoschedTunables {
+    /// Get the name of the active scheduler, such as `bfq` or `mq-deadline`.
+    pub fn scheduler(&self) -> Option<&str> {
+        self.scheduler.as_deref()
+    }
+    /// Whether the scheduler favors interactive and soft real-time applications over throughput
+    /// (`low_latency`, BFQ and CFQ).
+    pub fn low_latency(&self) -> Option<bool> {
+        self.low_latency
+    }
+    /// How long the scheduler idles on a queue waiting for more I/O before switching queues, which
+    /// is what makes priorities effective on rotational devices (`slice_idle`, BFQ and CFQ).
+    pub fn slice_idle(&self) -> Option<Duration> {
+        self.slice_idle
+    }
+    /// Whether BFQ trades throughput for stricter service guarantees (`strict_guarantees`).
+    pub fn strict_guarantees(&self) -> Option<bool> {
+        self.strict_guarantees
+    }
+    /// The maximum time BFQ serves a synchronous queue before switching (`timeout_sync`).
+    pub fn timeout_sync(&self) -> Option<Duration> {
+        self.timeout_sync
+    }
+    /// The base timeslice of synchronous queues, which CFQ scales by the best-effort level
+    /// (`slice_sync`).
+    pub fn slice_sync(&self) -> Option<Duration> {
+        self.slice_sync
+    }
+    /// The base timeslice of asynchronous queues, which CFQ scales by the best-effort level
+    /// (`slice_async`).
+    pub fn slice_async(&self) -> Option<Duration> {
+        self.slice_async
+    }
+    /// How long the scheduler idles on a cgroup before switching to another (`group_idle`, BFQ
+    /// and CFQ).
+    pub fn group_idle(&self) -> Option<Duration> {
+        self.group_idle
+    }
+}
+
+/// Read the tunables relevant to I/O priorities, of the active I/O scheduler of a block device.
+///
+/// The device is given either by its name (such as `sda`), or by its device node (such as
+/// `/dev/sda`), including symlinks such as `/dev/mapper/root`. Partitions, such as `sda1`, resolve
+/// to the whole disk, which is what the scheduler belongs to. The tunables are read from the
+/// `queue/iosched/` directory of the disk in sysfs, and are only present for the schedulers that
+/// have them; refer to [`IoschedTunables`]. An error is only returned if the device itself does
+/// not exist, or if reading any of the files fails for reasons other than the file not existing.
+pub fn read_iosched_tunables(device: &Path) -> io::Result<IoschedTunables> {
+    let queue = block_dir(device)?.join("queue");
+
+    let scheduler = fs::read_to_string(queue.join("scheduler"))?;
+    let iosched = queue.join("iosched");
+
+    let number = |name: &str| -> io::Result<Option<u64>> {
+        Ok(read_attr(&iosched.join(name))?.and_then(|value| u64::from_str(value.trim()).ok()))
+    };
+    let flag = |name: &str| number(name).map(|value| value.map(|value| value != 0));
+    let millis = |name: &str| number(name).map(|value| value.map(Duration::from_millis));
+
+    // Newer kernels additionally expose the idle slices with microsecond precision.
+    let slice_idle = match number("slice_idle_us")? {
+        Some(us) => Some(Duration::from_micros(us)),
+        None => millis("slice_idle")?,
+    };
+    let group_idle = match number("group_idle_us")? {
+        Some(us) => Some(Duration::from_micros(us)),
+        None => millis("group_idle")?,
+    };
+
+    Ok(IoschedTunables {
+        scheduler: parse_active_scheduler(&scheduler).map(str::to_owned),
+        low_latency: flag("low_latency")?,
+        slice_idle,
+        strict_guarantees: flag("strict_guarantees")?,
+        timeout_sync: millis("timeout_sync")?,
+        slice_sync: millis("slice_sync")?,
+        slice_async: millis("slice_async")?,
+        group_idle,
+    })
+}
+
+/// Whether the active scheduler, given the contents of a `queue/scheduler` file, honors I/O
+/// priorities.
+fn honors_ioprio(contents: &str) -> bool {
+    matches!(parse_active_scheduler(contents), Some("bfq") | Some("cfq"))
+}
+
+/// Determine whether the active I/O scheduler of a block device honors I/O priorities.
+///
+/// The device is given either by its name (such as `sda` or `sda1`), or by its device node (such
+/// as `/dev/sda1` or `/dev/disk/by-uuid/...`), where partitions resolve to the whole disk, like
+/// for [`read_iosched_tunables`]. Only BFQ and the legacy CFQ scheduler (removed in Linux 5.0)
+/// schedule I/O by priority level, whereas with schedulers such as `none` or `kyber`, setting a
+/// priority has no effect at all. Note that `mq-deadline` also distinguishes between the
+/// real-time, best-effort, and idle classes since Linux 5.14, but ignores the levels within them,
+/// and is thus not considered to honor priorities here.
+pub fn scheduler_honors_ioprio(device: &Path) -> io::Result<bool> {
+    let scheduler = fs::read_to_string(block_dir(device)?.join("queue").join("scheduler"))?;
+
+    Ok(honors_ioprio(&scheduler))
+}
+
+/// Get the sysfs directory of the whole disk backing an open file, from the `st_dev` of the file.
+fn fd_disk_dir(fd: RawFd) -> io::Result<PathBuf> {
+    let dev = fstat(fd)
+        .map_err(|error| {
+            let errno = error.as_errno().unwrap_or(Errno::UnknownErrno);
+            io::Error::from_raw_os_error(errno as i32)
+        })?
+        .st_dev;
+
+    whole_disk_dir(Path::new(&format!(
+        "/sys/dev/block/{}:{}",
+        major(dev),
+        minor(dev)
+    )))
+}
+
+/// Determine whether the active I/O scheduler of the block device backing an open file, honors I/O
+/// priorities, like [`scheduler_honors_ioprio`].
+///
+/// The device is found from the `st_dev` of the file, through `/sys/dev/block`, and if it is a
+/// partition, the scheduler of the whole disk is used. This fails with [`io::ErrorKind::NotFound`]
+/// if the file does not reside on a block device, such as on `tmpfs`, or on some filesystems that
+/// report a virtual device number, such as overlayfs and btrfs. Refer to
+/// [`ioprio_effective_for_fd`] for a variant that instead returns `false` in these cases.
+pub fn fd_scheduler_honors_ioprio(fd: RawFd) -> io::Result<bool> {
+    let scheduler = fs::read_to_string(fd_disk_dir(fd)?.join("queue").join("scheduler"))?;
+
+    Ok(honors_ioprio(&scheduler))
+}
+
+/// Determine whether I/O priorities take effect for I/O on an open file, based on the active I/O
+/// scheduler of the block device backing it.
+///
+/// This resolves the device in the same way as [`fd_scheduler_honors_ioprio`], but returns `false`
+/// rather than an error whenever the file is not backed by a block device with a scheduler, such
+/// as for files on `tmpfs` or pipes. Device-mapper devices are not scheduled themselves, and loop
+/// devices use the `none` scheduler by default, so both usually yield `false` regardless of the
+/// scheduler of the underlying disk. An error is only returned if `fstat` fails, or if reading
+/// sysfs fails for other reasons than a missing file.
+pub fn ioprio_effective_for_fd(fd: RawFd) -> io::Result<bool> {
+    match fd_scheduler_honors_ioprio(fd) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+        other => other,
+    }
+}
+
+/// A block device along with its active I/O scheduler, as returned by [`list_block_devices`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BlockDevice {
+    name: String,
+    parent: Option<String>,
+    scheduler: Option<String>,
+    honors_ioprio: bool,
+}
+impl BlockDevice {
+    /// Get the name of the device, such as `sda` or `nvme0n1p1`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Get the name of the whole disk, if this device is a partition of it.
+    pub fn parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+    /// Get the name of the active scheduler, such as `bfq` or `none`, which for partitions is the
+    /// scheduler of their disk.
+    ///
+    /// This is [`None`] if the device does not have a scheduler at all, as is the case for
+    /// device-mapper devices.
+    pub fn scheduler(&self) -> Option<&str> {
+        self.scheduler.as_deref()
+    }
+    /// Whether the active scheduler honors I/O priorities, as determined by
+    /// [`scheduler_honors_ioprio`].
+    pub fn honors_ioprio(&self) -> bool {
+        self.honors_ioprio
+    }
+}
+
+/// List every block device in `/sys/block`, along with their partitions and active schedulers.
+///
+/// Each disk is directly followed by its partitions, which are attributed the scheduler of the
+/// disk, since partitions do not have a scheduler of their own. The disks are sorted by name, as
+/// are the partitions of each disk. An error is only returned if `/sys/block` cannot be read, or if
+/// reading the scheduler of a device fails for other reasons than it not having one.
+pub fn list_block_devices() -> io::Result<Vec<BlockDevice>> {
+    let mut disks = fs::read_dir("/sys/block")?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+        .collect::<io::Result<Vec<_>>>()?;
+    disks.sort();
+
+    let mut devices = Vec::new();
+
+    for disk in disks {
+        let dir = Path::new("/sys/block").join(&disk);
+
+        let contents = read_attr(&dir.join("queue").join("scheduler"))?;
+        let scheduler = contents
+            .as_deref()
+            .and_then(parse_active_scheduler)
+            .map(str::to_owned);
+        let honors_ioprio = contents.as_deref().map(honors_ioprio).unwrap_or(false);
+
+        let mut partitions = fs::read_dir(&dir)?
+            .filter_map(|entry| {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(error) => return Some(Err(error)),
+                };
+                if entry.path().join("partition").exists() {
+                    Some(Ok(entry.file_name().to_string_lossy().into_owned()))
+                } else {
+                    None
+                }
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        partitions.sort();
+
+        devices.push(BlockDevice {
+            name: disk.clone(),
+            parent: None,
+            scheduler: scheduler.clone(),
+            honors_ioprio,
+        });
+        devices.extend(partitions.into_iter().map(|partition| BlockDevice {
+            name: partition,
+            parent: Some(disk.clone()),
+            scheduler: scheduler.clone(),
+            honors_ioprio,
+        }));
+    }
+    Ok(devices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn parses_the_active_scheduler() {
+        assert_eq!(
+            parse_active_scheduler("mq-deadline kyber [bfq] none\n"),
+            Some("bfq")
+        );
+        assert_eq!(parse_active_scheduler("[none] mq-deadline\n"), Some("none"));
+        assert_eq!(parse_active_scheduler("none\n"), Some("none"));
+        assert_eq!(parse_active_scheduler(""), None);
+        assert_eq!(parse_active_scheduler("mq-deadline none\n"), None);
+    }
+    #[test]
+    fn partitions_and_symlinks_resolve_to_the_whole_disk() {
+        use std::os::unix::fs::symlink;
+
+        let root = std::env::temp_dir().join(format!("ioprio-sysfs-{}", std::process::id()));
+        let sys = root.join("sys");
+        let disk = sys.join("devices/pci0000:00/block/sda");
+
+        fs::create_dir_all(disk.join("sda1")).unwrap();
+        fs::write(disk.join("sda1/partition"), "1\n").unwrap();
+        fs::create_dir_all(sys.join("class/block")).unwrap();
+        symlink(&disk, sys.join("class/block/sda")).unwrap();
+        symlink(disk.join("sda1"), sys.join("class/block/sda1")).unwrap();
+
+        fs::create_dir_all(root.join("dev/disk/by-uuid")).unwrap();
+        fs::write(root.join("dev/sda1"), "").unwrap();
+        symlink("../../sda1", root.join("dev/disk/by-uuid/1234")).unwrap();
+
+        let resolve = |device: &Path| block_dir_in(&sys, device).unwrap();
+        let disk = fs::canonicalize(&disk).unwrap();
+
+        assert_eq!(resolve(Path::new("sda")), disk);
+        assert_eq!(resolve(Path::new("sda1")), disk);
+        assert_eq!(resolve(&root.join("dev/sda1")), disk);
+        assert_eq!(resolve(&root.join("dev/disk/by-uuid/1234")), disk);
+        assert_eq!(
+            block_dir_in(&sys, Path::new("sdb")).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+    #[test]
+    fn only_bfq_and_cfq_honor_priorities() {
+        assert!(honors_ioprio("mq-deadline kyber [bfq] none\n"));
+        assert!(honors_ioprio("noop deadline [cfq]\n"));
+        assert!(honors_ioprio("cfq\n"));
+        assert!(!honors_ioprio("[mq-deadline] kyber bfq none\n"));
+        assert!(!honors_ioprio("none\n"));
+        assert!(!honors_ioprio(""));
+    }
+}
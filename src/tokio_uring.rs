@@ -0,0 +1,191 @@
+//! Reads and writes with an explicit I/O priority, for the `tokio-uring` runtime.
+//!
+//! The operations of `tokio-uring` itself, such as `File::read_at`, always submit their entries
+//! with an `ioprio` field of zero, meaning that the kernel issues them at the I/O priority of the
+//! runtime thread, which is shared by every task of the runtime. The operations here instead build
+//! the submission entries themselves, so that individual reads and writes can be given their own
+//! priority, and are submitted like any other `tokio-uring` operation.
+//!
+//! Only reads and writes honor the `ioprio` field of a submission entry, which includes their
+//! vectored and fixed-buffer variants. Other opcodes either ignore the field, or reuse it for
+//! unrelated flags, as do `accept`, `send` and `recv`. The priority is captured when the kernel
+//! consumes the entry, and has the same effect as the per-thread priority would, which means that
+//! it only affects I/O scheduling if the file is backed by a block device whose scheduler
+//! supports priorities.
+use std::io;
+use std::marker::PhantomData;
+use std::os::unix::io::AsRawFd;
+
+use io_uring_06_::cqueue::Entry;
+use io_uring_06_::{opcode, types};
+use tokio_uring_::buf::{BoundedBuf, BoundedBufMut};
+use tokio_uring_::{BufResult, OneshotOutputTransform, UnsubmittedOneshot};
+
+use super::*;
+
+/// An unsubmitted read with an I/O priority, as returned by [`read_at_with_priority`].
+pub type PrioritizedRead<F, B> =
+    UnsubmittedOneshot<PrioritizedOpData<F, B>, PrioritizedReadTransform<F, B>>;
+/// An unsubmitted write with an I/O priority, as returned by [`write_at_with_priority`].
+pub type PrioritizedWrite<F, B> =
+    UnsubmittedOneshot<PrioritizedOpData<F, B>, PrioritizedWriteTransform<F, B>>;
+
+/// The file and buffer of a prioritized operation, which are kept alive while it is in flight.
+pub struct PrioritizedOpData<F, B> {
+    _file: F,
+    buf: B,
+}
+
+/// Converts the completion of a [`PrioritizedRead`] into its result, along with the buffer.
+pub struct PrioritizedReadTransform<F, B> {
+    _marker: PhantomData<fn() -> (F, B)>,
+}
+impl<F, B: BoundedBufMut> OneshotOutputTransform for PrioritizedReadTransform<F, B> {
+    type Output = BufResult<usize, B>;
+    type StoredData = PrioritizedOpData<F, B>;
+
+    fn transform_oneshot_output(self, data: Self::StoredData, cqe: Entry) -> Self::Output {
+        let mut buf = data.buf;
+        let result = cqe_result(&cqe);
+
+        if let Ok(read) = result {
+            // SAFETY: the kernel has initialized this many bytes from the start of the buffer.
+            unsafe { buf.set_init(read) };
+        }
+        (result, buf)
+    }
+}
+
+/// Converts the completion of a [`PrioritizedWrite`] into its result, along with the buffer.
+pub struct PrioritizedWriteTransform<F, B> {
+    _marker: PhantomData<fn() -> (F, B)>,
+}
+impl<F, B> OneshotOutputTransform for PrioritizedWriteTransform<F, B> {
+    type Output = BufResult<usize, B>;
+    type StoredData = PrioritizedOpData<F, B>;
+
+    fn transform_oneshot_output(self, data: Self::StoredData, cqe: Entry) -> Self::Output {
+        (cqe_result(&cqe), data.buf)
+    }
+}
+
+fn cqe_result(cqe: &Entry) -> io::Result<usize> {
+    if cqe.result() >= 0 {
+        Ok(cqe.result() as usize)
+    } else {
+        Err(io::Error::from_raw_os_error(-cqe.result()))
+    }
+}
+
+/// Read from a file at an offset, with an I/O priority, filling the buffer up to its total
+/// capacity.
+///
+/// This is the equivalent of `File::read_at`, except that the file is anything with a raw file
+/// descriptor, such as an `Rc<tokio_uring::fs::File>`, which is kept alive until the read
+/// completes. The returned operation must be [submitted](UnsubmittedOneshot::submit) from within
+/// the `tokio-uring` runtime, and resolves to the number of bytes read along with the buffer.
+/// Invalid priorities are rejected by the kernel with `EINVAL`, once the read is submitted.
+pub fn read_at_with_priority<F, B>(
+    file: F,
+    mut buf: B,
+    pos: u64,
+    priority: Priority,
+) -> PrioritizedRead<F, B>
+where
+    F: AsRawFd + 'static,
+    B: BoundedBufMut,
+{
+    let fd = types::Fd(file.as_raw_fd());
+    let (ptr, len) = (buf.stable_mut_ptr(), buf.bytes_total());
+
+    UnsubmittedOneshot::new(
+        PrioritizedOpData { _file: file, buf },
+        PrioritizedReadTransform {
+            _marker: PhantomData,
+        },
+        opcode::Read::new(fd, ptr, len as _)
+            .offset(pos)
+            .ioprio(priority.inner())
+            .build(),
+    )
+}
+
+/// Write the initialized part of a buffer to a file at an offset, with an I/O priority.
+///
+/// This is the equivalent of `File::write_at`, with the same requirements as
+/// [`read_at_with_priority`], and resolves to the number of bytes written along with the buffer.
+pub fn write_at_with_priority<F, B>(
+    file: F,
+    buf: B,
+    pos: u64,
+    priority: Priority,
+) -> PrioritizedWrite<F, B>
+where
+    F: AsRawFd + 'static,
+    B: BoundedBuf,
+{
+    let fd = types::Fd(file.as_raw_fd());
+    let (ptr, len) = (buf.stable_ptr(), buf.bytes_init());
+
+    UnsubmittedOneshot::new(
+        PrioritizedOpData { _file: file, buf },
+        PrioritizedWriteTransform {
+            _marker: PhantomData,
+        },
+        opcode::Write::new(fd, ptr, len as _)
+            .offset(pos)
+            .ioprio(priority.inner())
+            .build(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use tokio_uring_::fs::File;
+
+    #[test]
+    fn prioritized_reads_and_writes_complete() {
+        // io_uring may be unavailable, or disabled through `kernel.io_uring_disabled`.
+        if let Err(error) = io_uring_06_::IoUring::new(2) {
+            eprintln!("skipping, since io_uring cannot be used here: {}", error);
+            return;
+        }
+        let path = std::env::temp_dir().join(format!("ioprio-tokio-uring-{}", std::process::id()));
+
+        tokio_uring_::start(async {
+            let file = Rc::new(File::create(&path).await.unwrap());
+
+            let (written, _) = write_at_with_priority(
+                Rc::clone(&file),
+                b"prioritized".to_vec(),
+                0,
+                Priority::idle(),
+            )
+            .submit()
+            .await;
+            assert_eq!(written.unwrap(), 11);
+
+            let file = Rc::new(File::open(&path).await.unwrap());
+
+            let (read, buf) = read_at_with_priority(
+                Rc::clone(&file),
+                Vec::with_capacity(32),
+                0,
+                Priority::idle(),
+            )
+            .submit()
+            .await;
+            assert_eq!(read.unwrap(), 11);
+            assert_eq!(buf, b"prioritized");
+
+            let invalid = Priority::from_inner(0xFFFF);
+            let (read, _) = read_at_with_priority(file, vec![0; 4], 0, invalid)
+                .submit()
+                .await;
+            assert_eq!(read.unwrap_err().raw_os_error(), Some(libc::EINVAL));
+        });
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,88 @@
+//! Polling the priority of a target for changes.
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::*;
+
+/// A handle to a background thread watching the priority of a target, created by
+/// [`watch_priority`].
+///
+/// Dropping the handle stops the watch, and waits for the thread to finish.
+#[derive(Debug)]
+pub struct PriorityWatcher {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<Result<()>>>,
+}
+impl PriorityWatcher {
+    /// Stop watching, and return the error that ended the watch early, if any.
+    ///
+    /// The target ceasing to exist is not considered an error.
+    pub fn stop(mut self) -> Result<()> {
+        self.stop.take();
+
+        match self.thread.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(payload)) => std::panic::resume_unwind(payload),
+            None => Ok(()),
+        }
+    }
+}
+impl Drop for PriorityWatcher {
+    fn drop(&mut self) {
+        self.stop.take();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Watch the priority of a target for changes, by polling it on a background thread.
+///
+/// The priority is first read on the calling thread, with any error returned immediately. After
+/// that, it is read once every `interval`, and the callback is invoked with the previous and new
+/// priorities whenever they differ. The watch ends either when the returned handle is dropped or
+/// [stopped](PriorityWatcher::stop), when the target no longer exists, or when reading the
+/// priority fails for any other reason.
+///
+/// The kernel does not provide any way of being notified when I/O priorities change, so changes
+/// that are reverted within a single interval are never observed. Since a PID of zero refers to
+/// the calling thread, [`Target::Process`] and [`Target::Thread`] with a zero PID are resolved to
+/// the TID of the calling thread, rather than the watching thread.
+pub fn watch_priority<F>(
+    target: Target,
+    interval: Duration,
+    mut callback: F,
+) -> Result<PriorityWatcher>
+where
+    F: FnMut(Priority, Priority) + Send + 'static,
+{
+    let target = match target {
+        Target::Process(pid) | Target::Thread(pid) if pid.as_raw() == 0 => Target::Thread(gettid()),
+        other => other,
+    };
+    let mut current = get_restorable_priority(target)?;
+    let (stop, stopped) = mpsc::channel::<()>();
+
+    let thread = thread::spawn(move || loop {
+        match stopped.recv_timeout(interval) {
+            Err(RecvTimeoutError::Timeout) => (),
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        let new = match get_restorable_priority(target) {
+            Ok(new) => new,
+            Err(Error::InvalidTarget) => return Ok(()),
+            Err(error) => return Err(error),
+        };
+        if new != current {
+            callback(current, new);
+            current = new;
+        }
+    });
+
+    Ok(PriorityWatcher {
+        stop: Some(stop),
+        thread: Some(thread),
+    })
+}
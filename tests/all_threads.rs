@@ -0,0 +1,42 @@
+//! This lives in its own test binary, since setting the priority of every thread in the process
+//! would otherwise also change the threads running other tests.
+#![cfg(feature = "std")]
+
+use std::sync::{mpsc, Arc, Barrier};
+use std::thread;
+
+use ioprio::{get_priority, set_all_threads_priority, Priority, Target};
+
+#[test]
+fn every_thread_of_the_process_is_set() {
+    let (target_sender, target_receiver) = mpsc::channel();
+    let barrier = Arc::new(Barrier::new(3));
+
+    let handles = (0..2)
+        .map(|_| {
+            let target_sender = target_sender.clone();
+            let barrier = Arc::clone(&barrier);
+
+            thread::spawn(move || {
+                target_sender.send(Target::current_thread()).unwrap();
+                barrier.wait();
+            })
+        })
+        .collect::<Vec<_>>();
+    let targets = (0..2)
+        .map(|_| target_receiver.recv().unwrap())
+        .collect::<Vec<_>>();
+
+    set_all_threads_priority(Priority::idle()).unwrap();
+
+    assert_eq!(get_priority(Target::current_thread()), Ok(Priority::idle()));
+
+    for &target in &targets {
+        assert_eq!(get_priority(target), Ok(Priority::idle()));
+    }
+    barrier.wait();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}